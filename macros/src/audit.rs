@@ -0,0 +1,119 @@
+// Audit columns, maintained by the generated SQL rather than by clients:
+//
+//     created_at, updated_at   now() on insert; updated_at again on every update
+//     created_by, updated_by   the request's `audit::Actor`, likewise
+//
+// They are left out of the create and patch models, so a client cannot set
+// them. The names can be changed with the `audit` option:
+//
+//     generate_structs_from_ddl!("ddl.sql", audit = { "updated_at" => "modified_at" });
+//
+// The actor travels as the transaction-local setting `rust_openapi.actor`,
+// which the generated handlers set from the `audit::Actor` that an
+// authentication middleware puts in the request extensions:
+//
+//     req.extensions_mut().insert(audit::Actor(user_id.to_string()));
+//
+// Without one the `*_by` columns are NULL. Code calling the repository
+// directly can use `audit::begin`, or set the setting itself.
+
+use crate::config::Config;
+use crate::list;
+use quote::quote;
+
+/// The audit roles, in the order their columns are written.
+pub const ROLES: &[&str] = &["created_at", "updated_at", "created_by", "updated_by"];
+
+const ACTOR: &str = "NULLIF(current_setting('rust_openapi.actor', true), '')";
+
+// The audit role of `column`, if any. Key columns are never audit columns.
+fn role(table: &parse::Table, column: &parse::Column, config: &Config) -> Option<&'static str> {
+    if table.is_primary_key(&column.name) {
+        return None;
+    }
+    let name = column.name.trim_matches('"');
+    ROLES
+        .iter()
+        .find(|role| config.audit_column(role).eq_ignore_ascii_case(name))
+        .copied()
+}
+
+/// Whether `column` is maintained server-side and hidden from client input.
+pub fn is_audit(table: &parse::Table, column: &parse::Column, config: &Config) -> bool {
+    role(table, column, config).is_some()
+}
+
+// `now()`, or the actor cast to the column's type.
+fn value(role: &str, column: &parse::Column, schema: &parse::Schema) -> String {
+    if role.ends_with("_at") {
+        String::from("now()")
+    } else {
+        format!(
+            "CAST({} AS {})",
+            ACTOR,
+            list::cast_type(&column.dtype, schema)
+        )
+    }
+}
+
+/// Audit columns set by an INSERT, with their SQL values.
+pub fn on_insert(
+    table: &parse::Table,
+    schema: &parse::Schema,
+    config: &Config,
+) -> Vec<(String, String)> {
+    table
+        .columns
+        .iter()
+        .filter_map(|column| {
+            let role = role(table, column, config)?;
+            Some((column.name.clone(), value(role, column, schema)))
+        })
+        .collect()
+}
+
+/// Audit columns set by an UPDATE, with their SQL values.
+pub fn on_update(
+    table: &parse::Table,
+    schema: &parse::Schema,
+    config: &Config,
+) -> Vec<(String, String)> {
+    table
+        .columns
+        .iter()
+        .filter_map(|column| {
+            let role = role(table, column, config).filter(|role| role.starts_with("updated_"))?;
+            Some((column.name.clone(), value(role, column, schema)))
+        })
+        .collect()
+}
+
+/// `pub mod audit`, carrying the request's identity into the transaction.
+pub fn support() -> proc_macro2::TokenStream {
+    quote! {
+        pub mod audit {
+            use super::PgPool;
+
+            /// Who is making the request, as written to `created_by` and
+            /// `updated_by`. Insert it into the request extensions.
+            #[derive(Debug, Clone)]
+            pub struct Actor(pub String);
+
+            /// Begins a transaction whose audit columns name the request's `Actor`.
+            pub async fn begin(
+                pool: &PgPool,
+                req: &actix_web::HttpRequest,
+            ) -> Result<sqlx::Transaction<'static, sqlx::Postgres>, sqlx::Error> {
+                let actor = actix_web::HttpMessage::extensions(req).get::<Actor>().cloned();
+                let mut tx = pool.begin().await?;
+                if let Some(Actor(actor)) = actor {
+                    sqlx::query("SELECT set_config('rust_openapi.actor', $1, true)")
+                        .bind(actor)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+                Ok(tx)
+            }
+        }
+    }
+}
@@ -0,0 +1,206 @@
+// `POST /batch` runs an ordered list of operations on any generated table in
+// one transaction:
+//
+//     [
+//       { "op": "create", "table": "assets", "ref": "laptop", "body": { "asset_name": "laptop" } },
+//       { "op": "create", "table": "asset_collection",
+//         "body": { "asset_id": { "$ref": "laptop.asset_id" }, "collection_id": "..." } },
+//       { "op": "update", "table": "assets", "key": { "asset_id": "..." }, "body": { ... } },
+//       { "op": "delete", "table": "assets", "key": { "asset_id": "..." } }
+//     ]
+//
+// An operation with a `ref` names the row it returns, and later `key` or
+// `body` fields may take a column of that row with `{ "$ref": "name.column" }`.
+// The answer is one `{ "status", "body" }` per operation, in order. The first
+// failure rolls everything back and is answered with its own status, plus an
+// `errors` entry giving the index of the operation.
+
+use crate::config::Config;
+use crate::repository::Names;
+use quote::quote;
+
+/// The `batch` runtime module, the per-table dispatch and the handler.
+pub fn batch(schema: &parse::Schema, config: &Config) -> proc_macro2::TokenStream {
+    let arms = schema.tables.iter().map(|table| {
+        let Names {
+            new_model,
+            patch,
+            key,
+            repository,
+            ..
+        } = &Names::new(table, config);
+        let table_name = &table.name;
+        let not_found = format!("{} not found", table.name);
+        let by_key = if table.primary_key().is_empty() {
+            let no_key = format!("{} has no primary key to update or delete by", table.name);
+            quote! {
+                _ => Err(ApiError::bad_request(#no_key)),
+            }
+        } else {
+            quote! {
+                batch::Op::Update => {
+                    let key: #key = batch::decode(operation.key)?;
+                    let patch: #patch = batch::decode(operation.body)?;
+                    match #repository::update(&mut *conn, &key, &patch).await? {
+                        Some(row) => Ok(batch::Outcome::row(200, &row)),
+                        None => Err(ApiError::not_found(#not_found)),
+                    }
+                }
+                batch::Op::Delete => {
+                    let key: #key = batch::decode(operation.key)?;
+                    if !#repository::delete(&mut *conn, &key).await? {
+                        return Err(ApiError::not_found(#not_found));
+                    }
+                    Ok(batch::Outcome { status: 204, body: None })
+                }
+            }
+        };
+        quote! {
+            #table_name => match operation.op {
+                batch::Op::Create => {
+                    let new: #new_model = batch::decode(operation.body)?;
+                    let row = #repository::insert(&mut *conn, &new).await?;
+                    Ok(batch::Outcome::row(201, &row))
+                }
+                #by_key
+            },
+        }
+    });
+
+    quote! {
+        pub mod batch {
+            use super::{list_query::Object, ApiError, Deserialize, RowError, Serialize};
+            use std::collections::HashMap;
+
+            #[derive(Deserialize, Debug, Clone, Copy)]
+            #[serde(rename_all = "lowercase")]
+            pub enum Op {
+                Create,
+                Update,
+                Delete,
+            }
+
+            /// One entry of a `POST /batch` body.
+            #[derive(Deserialize, Debug)]
+            #[serde(deny_unknown_fields)]
+            pub struct Operation {
+                pub op: Op,
+                pub table: String,
+                /// Name later operations use to refer to the returned row.
+                #[serde(rename = "ref")]
+                pub name: Option<String>,
+                #[serde(default)]
+                pub key: Object,
+                #[serde(default)]
+                pub body: Object,
+            }
+
+            /// What one operation did: its status and the row it returned, if any.
+            #[derive(Serialize, Debug)]
+            pub struct Outcome {
+                pub status: u16,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                pub body: Option<Object>,
+            }
+
+            impl Outcome {
+                pub fn row<T: Serialize>(status: u16, row: &T) -> Self {
+                    Outcome {
+                        status,
+                        body: Some(super::list_query::object(row)),
+                    }
+                }
+            }
+
+            /// Replaces `{ "$ref": "name.column" }` values with the column of a named row.
+            pub fn resolve(fields: &mut Object, named: &HashMap<String, Object>) -> Result<(), ApiError> {
+                for value in fields.values_mut() {
+                    let reference = match value {
+                        serde_json::Value::Object(map) if map.len() == 1 => {
+                            match map.get("$ref").and_then(|r| r.as_str()) {
+                                Some(reference) => reference.to_string(),
+                                None => continue,
+                            }
+                        }
+                        _ => continue,
+                    };
+                    let (name, column) = reference.split_once('.').ok_or_else(|| {
+                        ApiError::bad_request(format!("`$ref` must be `name.column`, got `{}`", reference))
+                    })?;
+                    let row = named.get(name).ok_or_else(|| {
+                        ApiError::bad_request(format!("no earlier operation has ref `{}`", name))
+                    })?;
+                    *value = row.get(column).cloned().ok_or_else(|| {
+                        ApiError::bad_request(format!("row `{}` has no column `{}`", name, column))
+                    })?;
+                }
+                Ok(())
+            }
+
+            pub fn decode<T: serde::de::DeserializeOwned>(fields: Object) -> Result<T, ApiError> {
+                serde_json::from_value(serde_json::Value::Object(fields))
+                    .map_err(|e| ApiError::bad_request(e.to_string()))
+            }
+
+            /// The error of the operation at `index`, which rolled the batch back.
+            pub fn failed(index: usize, error: ApiError) -> ApiError {
+                let detail = format!("operation {} failed, nothing was changed", index);
+                ApiError {
+                    errors: vec![RowError {
+                        index,
+                        status: error.status,
+                        detail: error.detail.clone(),
+                    }],
+                    detail: Some(detail),
+                    ..error
+                }
+            }
+        }
+
+        async fn batch_operation(
+            conn: &mut sqlx::PgConnection,
+            mut operation: batch::Operation,
+            named: &std::collections::HashMap<String, list_query::Object>,
+        ) -> Result<batch::Outcome, ApiError> {
+            if let Some(name) = &operation.name {
+                if named.contains_key(name) {
+                    return Err(ApiError::bad_request(format!("ref `{}` is already used", name)));
+                }
+            }
+            batch::resolve(&mut operation.key, named)?;
+            batch::resolve(&mut operation.body, named)?;
+            match operation.table.as_str() {
+                #(#arms)*
+                table => Err(ApiError::bad_request(format!("unknown table `{}`", table))),
+            }
+        }
+
+        #[post("/batch")]
+        async fn batch_handler(
+            req: actix_web::HttpRequest,
+            operations: web::Json<Vec<batch::Operation>>,
+            pool: web::Data<PgPool>,
+        ) -> Result<HttpResponse, ApiError> {
+            bulk::check_size(operations.len())?;
+            let mut tx = audit::begin(&pool, &req).await?;
+            let mut named = std::collections::HashMap::new();
+            let mut outcomes = Vec::with_capacity(operations.len());
+            for (index, operation) in operations.into_inner().into_iter().enumerate() {
+                let name = operation.name.clone();
+                let outcome = match batch_operation(&mut tx, operation, &named).await {
+                    Ok(outcome) => outcome,
+                    Err(error) => {
+                        tx.rollback().await?;
+                        return Err(batch::failed(index, error));
+                    }
+                };
+                if let (Some(name), Some(row)) = (name, &outcome.body) {
+                    named.insert(name, row.clone());
+                }
+                outcomes.push(outcome);
+            }
+            tx.commit().await?;
+            Ok(HttpResponse::Ok().json(outcomes))
+        }
+    }
+}
@@ -0,0 +1,207 @@
+// Bulk endpoints, each run in a single transaction:
+//
+//     POST   /assets/bulk              [{ "asset_name": "a" }, ...]          -> 201, the rows
+//     PATCH  /assets/bulk              [{ "asset_id": "..", "asset_name": "b" }, ...] -> 200
+//     DELETE /assets?asset_brand=ibm                                        -> 200 { "deleted": n }
+//
+// Bodies are limited to `max_batch_size` rows (413 beyond that), and a
+// filtered DELETE matching more rows than that is rolled back with a 422.
+// DELETE without a filter is refused rather than emptying the table.
+//
+// Nothing is written unless every row succeeds. Otherwise the answer is a 422
+// whose `errors` member lists each failed row by its index in the body:
+//
+//     { "status": 422, "detail": "2 row(s) failed, nothing was changed",
+//       "errors": [{ "index": 3, "status": 409, "detail": "..." }, ...] }
+//
+// Inserts go through one multi-row INSERT per chunk; only when that fails are
+// the rows replayed one at a time, each under a savepoint, to find the culprits.
+
+use crate::config::Config;
+use crate::repository::{self, Names};
+use quote::quote;
+
+/// `pub mod bulk`, shared by the bulk handlers of every table.
+pub fn support(schema: &parse::Schema, config: &Config) -> proc_macro2::TokenStream {
+    let max_batch_size = config.max_batch_size() as usize;
+    let keyed = if schema
+        .tables
+        .iter()
+        .any(|table| !table.primary_key().is_empty())
+    {
+        quote! {
+            /// One row of a bulk `PATCH`: the primary key next to the fields to change.
+            #[derive(Deserialize, Debug)]
+            pub struct Keyed<K, P> {
+                #[serde(flatten)]
+                pub key: K,
+                #[serde(flatten)]
+                pub patch: P,
+            }
+        }
+    } else {
+        quote! {}
+    };
+    quote! {
+        pub mod bulk {
+            use super::{ApiError, Deserialize};
+
+            /// Most rows a bulk request may create, update or delete.
+            pub const MAX_BATCH_SIZE: usize = #max_batch_size;
+
+            #keyed
+
+            /// 413 when a bulk body has more than `MAX_BATCH_SIZE` rows.
+            pub fn check_size(rows: usize) -> Result<(), ApiError> {
+                if rows > MAX_BATCH_SIZE {
+                    return Err(ApiError::new(
+                        actix_web::http::StatusCode::PAYLOAD_TOO_LARGE,
+                        Some(format!("{} rows sent, at most {} are allowed", rows, MAX_BATCH_SIZE)),
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn ident(name: String) -> syn::Ident {
+    syn::Ident::new(&name, proc_macro2::Span::call_site())
+}
+
+/// Bulk handlers for `table`, and their names in registration order.
+pub fn routes(
+    table: &parse::Table,
+    config: &Config,
+) -> (proc_macro2::TokenStream, Vec<syn::Ident>) {
+    let mut output = proc_macro2::TokenStream::new();
+    let mut services = Vec::new();
+    let Names {
+        new_model,
+        patch,
+        key,
+        repository,
+        ..
+    } = &Names::new(table, config);
+    let lower = table.name.trim_matches('"').to_lowercase();
+    let route = format!("/{}", table.name);
+    let bulk_route = format!("/{}/bulk", table.name);
+
+    // Tables without client-supplied columns have no `insert_many`.
+    if !repository::insert_columns(table, config).is_empty() {
+        let post = ident(format!("post_{}_bulk_handler", lower));
+        output.extend(quote! {
+            #[post(#bulk_route)]
+            async fn #post(
+                req: actix_web::HttpRequest,
+                rows: web::Json<Vec<#new_model>>,
+                pool: web::Data<PgPool>,
+            ) -> Result<HttpResponse, ApiError> {
+                bulk::check_size(rows.len())?;
+                let mut tx = audit::begin(&pool, &req).await?;
+                let mut created = Vec::with_capacity(rows.len());
+                for chunk in rows.chunks(#repository::INSERT_CHUNK) {
+                    let error = match #repository::insert_many(&mut *tx, chunk).await {
+                        Ok(inserted) => {
+                            created.extend(inserted);
+                            continue;
+                        }
+                        Err(error) => error,
+                    };
+                    tx.rollback().await?;
+                    // Replay the rows one at a time to report every one that fails.
+                    let mut tx = audit::begin(&pool, &req).await?;
+                    let mut errors = Vec::new();
+                    for (index, row) in rows.iter().enumerate() {
+                        let mut savepoint = sqlx::Connection::begin(&mut *tx).await?;
+                        match #repository::insert(&mut *savepoint, row).await {
+                            Ok(_) => savepoint.commit().await?,
+                            Err(error) => {
+                                savepoint.rollback().await?;
+                                errors.push(RowError::new(index, error.into()));
+                            }
+                        }
+                    }
+                    tx.rollback().await?;
+                    if errors.is_empty() {
+                        return Err(error.into());
+                    }
+                    return Err(ApiError::rows(errors));
+                }
+                tx.commit().await?;
+                Ok(HttpResponse::Created().json(created))
+            }
+        });
+        services.push(post);
+    }
+
+    if !table.primary_key().is_empty() {
+        let not_found = format!("{} not found", table.name);
+        let patch_handler = ident(format!("patch_{}_bulk_handler", lower));
+        output.extend(quote! {
+            #[patch(#bulk_route)]
+            async fn #patch_handler(
+                req: actix_web::HttpRequest,
+                rows: web::Json<Vec<bulk::Keyed<#key, #patch>>>,
+                pool: web::Data<PgPool>,
+            ) -> Result<HttpResponse, ApiError> {
+                bulk::check_size(rows.len())?;
+                let mut tx = audit::begin(&pool, &req).await?;
+                let mut updated = Vec::with_capacity(rows.len());
+                let mut errors = Vec::new();
+                for (index, row) in rows.iter().enumerate() {
+                    let mut savepoint = sqlx::Connection::begin(&mut *tx).await?;
+                    match #repository::update(&mut *savepoint, &row.key, &row.patch).await {
+                        Ok(Some(row)) => updated.push(row),
+                        Ok(None) => errors.push(RowError::new(index, ApiError::not_found(#not_found))),
+                        Err(error) => {
+                            savepoint.rollback().await?;
+                            errors.push(RowError::new(index, error.into()));
+                            continue;
+                        }
+                    }
+                    savepoint.commit().await?;
+                }
+                if !errors.is_empty() {
+                    tx.rollback().await?;
+                    return Err(ApiError::rows(errors));
+                }
+                tx.commit().await?;
+                Ok(HttpResponse::Ok().json(updated))
+            }
+        });
+        services.push(patch_handler);
+    }
+
+    let delete = ident(format!("delete_{}_handler", lower));
+    output.extend(quote! {
+        #[delete(#route)]
+        async fn #delete(
+            req: actix_web::HttpRequest,
+            pool: web::Data<PgPool>,
+        ) -> Result<HttpResponse, ApiError> {
+            let list = list_query::ListQuery::parse(req.query_string(), &#repository::TABLE)?;
+            if list.filters.is_empty() {
+                return Err(ApiError::bad_request("a filter is required, e.g. `?column=value`"));
+            }
+            let mut tx = audit::begin(&pool, &req).await?;
+            let deleted = #repository::delete_where(&mut *tx, &list).await?;
+            if deleted > bulk::MAX_BATCH_SIZE as u64 {
+                tx.rollback().await?;
+                return Err(ApiError::new(
+                    actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
+                    Some(format!(
+                        "{} rows match, at most {} may be deleted at once",
+                        deleted,
+                        bulk::MAX_BATCH_SIZE
+                    )),
+                ));
+            }
+            tx.commit().await?;
+            Ok(HttpResponse::Ok().json(serde_json::json!({ "deleted": deleted })))
+        }
+    });
+    services.push(delete);
+
+    (output, services)
+}
@@ -0,0 +1,474 @@
+// Configuration for `generate_structs_from_ddl`, read from `rust-openapi.toml`
+// next to Cargo.toml and from inline arguments, which take precedence:
+//
+//     generate_structs_from_ddl!(
+//         "../openapi/ddl.sql",
+//         prefix = "/api/v1",
+//         page_size = 50,
+//         max_page_size = 500,
+//         max_expand_depth = 2,
+//         max_batch_size = 1000,
+//         types = { "numeric" => f64 },
+//         columns = { "users.*_email" => crate::Email },
+//         etags = { "collections" => "version", "brands" => "hash" },
+//         audit = { "updated_at" => "modified_at" },
+//         soft_delete = { "assets" => "removed_at" },
+//         models = { "news" => "NewsItem" },
+//     );
+//
+//     # rust-openapi.toml
+//     prefix = "/api/v1"
+//     page_size = 50
+//     max_page_size = 500
+//     max_expand_depth = 2
+//     max_batch_size = 1000
+//     [types]
+//     numeric = "f64"
+//     [columns]
+//     "users.*_email" = "crate::Email"
+//     [etags]
+//     collections = "version"
+//     brands = "hash"
+//     [audit]
+//     updated_at = "modified_at"
+//     [soft_delete]
+//     assets = "removed_at"
+//     [models]
+//     news = "NewsItem"
+//
+// `etags` names the column whose value is a table's ETag, or `hash` to hash
+// the whole row. Unlisted tables use a `version` column, then `updated_at`,
+// then the row hash.
+//
+// `audit` renames the created_at, updated_at, created_by and updated_by
+// columns the generated SQL maintains; see `audit.rs`.
+//
+// `soft_delete` names the timestamp column that marks a table's rows as
+// deleted. Unlisted tables use a nullable `deleted_at` column if they have
+// one; see `soft_delete.rs`.
+//
+// `models` names the structs generated for a table, where singularising the
+// table name gets it wrong. `NewsItem` also gives `NewNewsItem`,
+// `NewsItemPatch`, `NewsItemKey` and `NewsItemRepository`.
+
+use quote::quote;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{braced, Ident, LitInt, LitStr, Token};
+
+const CONFIG_FILE: &str = "rust-openapi.toml";
+const OPTIONS: &[&str] = &[
+    "config",
+    "prefix",
+    "page_size",
+    "max_page_size",
+    "max_expand_depth",
+    "max_batch_size",
+    "types",
+    "columns",
+    "etags",
+    "audit",
+    "soft_delete",
+    "models",
+];
+const DEFAULT_PAGE_SIZE: u32 = 50;
+const DEFAULT_MAX_PAGE_SIZE: u32 = 500;
+const DEFAULT_MAX_EXPAND_DEPTH: u32 = 2;
+const DEFAULT_MAX_BATCH_SIZE: u32 = 1000;
+
+#[derive(Default)]
+pub struct Config {
+    /// SQL type -> Rust type, e.g. `numeric` -> `f64`.
+    pub types: Vec<(String, syn::Type)>,
+    /// `table.column` pattern (with `*` wildcards) -> Rust type.
+    pub columns: Vec<(String, syn::Type)>,
+    /// Table -> ETag source column, or `hash`.
+    pub etags: Vec<(String, String)>,
+    /// Audit role (`created_at`, ...) -> column name, where not the default.
+    pub audit: Vec<(String, String)>,
+    /// Table -> soft delete column, where not `deleted_at`.
+    pub soft_delete: Vec<(String, String)>,
+    /// Table -> model struct name, where not derived from the table name.
+    pub models: Vec<(String, String)>,
+    /// Path prefix for the routes registered by the generated `configure`.
+    pub prefix: Option<String>,
+    /// Rows per page when a list request has no `limit`.
+    pub page_size: Option<u32>,
+    /// Upper bound for `limit` on list requests.
+    pub max_page_size: Option<u32>,
+    /// How many levels `?expand=` may follow foreign keys; 0 disables it.
+    pub max_expand_depth: Option<u32>,
+    /// Most rows a bulk request may create, update or delete.
+    pub max_batch_size: Option<u32>,
+    /// The config file that was read, tracked so edits to it recompile.
+    pub file: Option<PathBuf>,
+}
+
+pub struct MacroInput {
+    pub path: LitStr,
+    pub config_file: Option<LitStr>,
+    pub config: Config,
+}
+
+struct TypeMapping {
+    key: LitStr,
+    ty: syn::Type,
+}
+
+impl Parse for TypeMapping {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let ty = input.parse()?;
+        Ok(TypeMapping { key, ty })
+    }
+}
+
+fn parse_mappings(input: ParseStream) -> syn::Result<Vec<(String, syn::Type)>> {
+    let content;
+    braced!(content in input);
+    let mappings = Punctuated::<TypeMapping, Token![,]>::parse_terminated(&content)?;
+    Ok(mappings
+        .into_iter()
+        .map(|m| (m.key.value(), m.ty))
+        .collect())
+}
+
+struct NameMapping {
+    key: LitStr,
+    value: LitStr,
+}
+
+impl Parse for NameMapping {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let value = input.parse()?;
+        Ok(NameMapping { key, value })
+    }
+}
+
+fn parse_names(input: ParseStream) -> syn::Result<Vec<(String, String)>> {
+    let content;
+    braced!(content in input);
+    let mappings = Punctuated::<NameMapping, Token![,]>::parse_terminated(&content)?;
+    Ok(mappings
+        .into_iter()
+        .map(|m| (m.key.value(), m.value.value()))
+        .collect())
+}
+
+impl Parse for MacroInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path = input.parse()?;
+        let mut config_file = None;
+        let mut config = Config::default();
+        while input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let key: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            match key.to_string().as_str() {
+                "config" => config_file = Some(input.parse()?),
+                "types" => config.types.extend(parse_mappings(input)?),
+                "columns" => config.columns.extend(parse_mappings(input)?),
+                "etags" => config.etags.extend(parse_names(input)?),
+                "audit" => config.audit.extend(parse_names(input)?),
+                "soft_delete" => config.soft_delete.extend(parse_names(input)?),
+                "models" => config.models.extend(parse_names(input)?),
+                "prefix" => config.prefix = Some(input.parse::<LitStr>()?.value()),
+                "page_size" => config.page_size = Some(input.parse::<LitInt>()?.base10_parse()?),
+                "max_page_size" => {
+                    config.max_page_size = Some(input.parse::<LitInt>()?.base10_parse()?)
+                }
+                "max_expand_depth" => {
+                    config.max_expand_depth = Some(input.parse::<LitInt>()?.base10_parse()?)
+                }
+                "max_batch_size" => {
+                    config.max_batch_size = Some(input.parse::<LitInt>()?.base10_parse()?)
+                }
+                other => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!(
+                            "unknown option `{}`, expected one of `{}`",
+                            other,
+                            OPTIONS.join("`, `")
+                        ),
+                    ))
+                }
+            }
+        }
+        Ok(MacroInput {
+            path,
+            config_file,
+            config,
+        })
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    prefix: Option<String>,
+    page_size: Option<u32>,
+    max_page_size: Option<u32>,
+    max_expand_depth: Option<u32>,
+    max_batch_size: Option<u32>,
+    #[serde(default)]
+    types: BTreeMap<String, String>,
+    #[serde(default)]
+    columns: BTreeMap<String, String>,
+    #[serde(default)]
+    etags: BTreeMap<String, String>,
+    #[serde(default)]
+    audit: BTreeMap<String, String>,
+    #[serde(default)]
+    soft_delete: BTreeMap<String, String>,
+    #[serde(default)]
+    models: BTreeMap<String, String>,
+}
+
+fn parse_type(value: &str, key: &str, file: &Path) -> syn::Result<syn::Type> {
+    syn::parse_str(value).map_err(|_| {
+        syn::Error::new(
+            proc_macro2::Span::call_site(),
+            format!(
+                "invalid Rust type `{}` for `{}` in {}",
+                value,
+                key,
+                file.display()
+            ),
+        )
+    })
+}
+
+// Keeps `base` entries unless `overrides` has the same key.
+fn merge<T>(base: &mut Vec<(String, T)>, overrides: Vec<(String, T)>) {
+    for (key, value) in overrides {
+        base.retain(|(k, _)| !k.eq_ignore_ascii_case(&key));
+        base.push((key, value));
+    }
+}
+
+// Matches `*` against any run of characters, case-insensitively.
+fn glob_match(pattern: &str, value: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let value = value.to_lowercase();
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = value.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        if i == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.is_empty()
+}
+
+impl Config {
+    /// Reads `rust-openapi.toml` (or the file named by `config = "..."`) and
+    /// layers the inline arguments on top of it.
+    pub fn load(manifest_dir: &Path, input: MacroInput) -> syn::Result<Config> {
+        let explicit = input.config_file.is_some();
+        let file = manifest_dir.join(
+            input
+                .config_file
+                .as_ref()
+                .map_or(CONFIG_FILE.to_string(), LitStr::value),
+        );
+        let mut config = Config::default();
+        if explicit || file.exists() {
+            let content = fs::read_to_string(&file).map_err(|e| {
+                syn::Error::new(
+                    proc_macro2::Span::call_site(),
+                    format!("unable to read {}: {}", file.display(), e),
+                )
+            })?;
+            let parsed: ConfigFile = toml::from_str(&content).map_err(|e| {
+                syn::Error::new(
+                    proc_macro2::Span::call_site(),
+                    format!("invalid {}: {}", file.display(), e),
+                )
+            })?;
+            config.file = Some(file.clone());
+            config.prefix = parsed.prefix;
+            config.page_size = parsed.page_size;
+            config.max_page_size = parsed.max_page_size;
+            config.max_expand_depth = parsed.max_expand_depth;
+            config.max_batch_size = parsed.max_batch_size;
+            for (key, value) in parsed.types {
+                let ty = parse_type(&value, &key, &file)?;
+                config.types.push((key, ty));
+            }
+            for (key, value) in parsed.columns {
+                let ty = parse_type(&value, &key, &file)?;
+                config.columns.push((key, ty));
+            }
+            config.etags.extend(parsed.etags);
+            config.audit.extend(parsed.audit);
+            config.soft_delete.extend(parsed.soft_delete);
+            config.models.extend(parsed.models);
+        }
+        merge(&mut config.types, input.config.types);
+        merge(&mut config.columns, input.config.columns);
+        merge(&mut config.etags, input.config.etags);
+        merge(&mut config.audit, input.config.audit);
+        merge(&mut config.soft_delete, input.config.soft_delete);
+        merge(&mut config.models, input.config.models);
+        if let Some((role, _)) = config
+            .audit
+            .iter()
+            .find(|(role, _)| !crate::audit::ROLES.contains(&role.as_str()))
+        {
+            return Err(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                format!(
+                    "unknown audit column `{}`, expected one of `{}`",
+                    role,
+                    crate::audit::ROLES.join("`, `")
+                ),
+            ));
+        }
+        if let Some((table, name)) = config
+            .models
+            .iter()
+            .find(|(_, name)| syn::parse_str::<Ident>(name).is_err())
+        {
+            return Err(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                format!("invalid model name `{}` for `{}`", name, table),
+            ));
+        }
+        if input.config.prefix.is_some() {
+            config.prefix = input.config.prefix;
+        }
+        if input.config.page_size.is_some() {
+            config.page_size = input.config.page_size;
+        }
+        if input.config.max_page_size.is_some() {
+            config.max_page_size = input.config.max_page_size;
+        }
+        if input.config.max_expand_depth.is_some() {
+            config.max_expand_depth = input.config.max_expand_depth;
+        }
+        if input.config.max_batch_size.is_some() {
+            config.max_batch_size = input.config.max_batch_size;
+        }
+        if config.page_size() == 0 || config.page_size() > config.max_page_size() {
+            return Err(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                format!(
+                    "page_size must be between 1 and max_page_size ({})",
+                    config.max_page_size()
+                ),
+            ));
+        }
+        Ok(config)
+    }
+
+    /// Override for a normalised SQL type name such as `numeric` or `_text`.
+    pub fn type_override(&self, dtype: &str) -> Option<&syn::Type> {
+        self.types
+            .iter()
+            .find(|(key, _)| crate::types::normalize(key) == dtype)
+            .map(|(_, ty)| ty)
+    }
+
+    /// Override for `table.column`. An exact entry wins over wildcard
+    /// patterns, and longer patterns win over shorter ones.
+    pub fn column_override(&self, table: &str, column: &str) -> Option<&syn::Type> {
+        let target = format!("{}.{}", table.trim_matches('"'), column.trim_matches('"'));
+        self.columns
+            .iter()
+            .filter(|(pattern, _)| glob_match(pattern, &target))
+            .max_by_key(|(pattern, _)| (!pattern.contains('*'), pattern.len()))
+            .map(|(_, ty)| ty)
+    }
+
+    /// The configured ETag source of `table`: a column name or `hash`.
+    pub fn etag_source(&self, table: &str) -> Option<&str> {
+        self.etags
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(table.trim_matches('"')))
+            .map(|(_, source)| source.as_str())
+    }
+
+    /// The configured soft delete column of `table`.
+    pub fn soft_delete_column(&self, table: &str) -> Option<&str> {
+        self.soft_delete
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(table.trim_matches('"')))
+            .map(|(_, column)| column.as_str())
+    }
+
+    /// The configured model struct name of `table`.
+    pub fn model_name(&self, table: &str) -> Option<&str> {
+        self.models
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(table.trim_matches('"')))
+            .map(|(_, name)| name.as_str())
+    }
+
+    /// Column name for an audit role, `created_at` unless renamed.
+    pub fn audit_column<'a>(&'a self, role: &'a str) -> &'a str {
+        self.audit
+            .iter()
+            .find(|(key, _)| key == role)
+            .map_or(role, |(_, column)| column.as_str())
+    }
+
+    pub fn page_size(&self) -> u32 {
+        self.page_size
+            .unwrap_or(DEFAULT_PAGE_SIZE.min(self.max_page_size()))
+    }
+
+    pub fn max_page_size(&self) -> u32 {
+        self.max_page_size.unwrap_or(DEFAULT_MAX_PAGE_SIZE)
+    }
+
+    pub fn max_expand_depth(&self) -> u32 {
+        self.max_expand_depth.unwrap_or(DEFAULT_MAX_EXPAND_DEPTH)
+    }
+
+    pub fn max_batch_size(&self) -> u32 {
+        self.max_batch_size.unwrap_or(DEFAULT_MAX_BATCH_SIZE)
+    }
+
+    /// The configured prefix as a scope path: leading `/`, no trailing `/`,
+    /// `None` when unset or just `/`.
+    pub fn route_prefix(&self) -> Option<String> {
+        let prefix = self.prefix.as_deref()?.trim_matches('/');
+        if prefix.is_empty() {
+            None
+        } else {
+            Some(format!("/{}", prefix))
+        }
+    }
+
+    /// Forces every configured type to resolve, so a misspelt override is a
+    /// compile error even when no column currently uses it.
+    pub fn type_checks(&self) -> proc_macro2::TokenStream {
+        let types = self
+            .types
+            .iter()
+            .chain(self.columns.iter())
+            .map(|(_, ty)| ty);
+        quote! {
+            #(const _: Option<#types> = None;)*
+        }
+    }
+}
@@ -0,0 +1,167 @@
+// Error type shared by the handlers `generate_structs_from_ddl` emits.
+//
+// Failures are rendered as RFC 7807 Problem Details
+// (`application/problem+json`), and sqlx errors are mapped to a status code
+// in one place:
+//
+//     23505 unique_violation                 -> 409 Conflict
+//     23503/23502/23514 FK, NOT NULL, CHECK  -> 422 Unprocessable Entity
+//     22xxx data exceptions (bad input)      -> 400 Bad Request
+//     RowNotFound                            -> 404 Not Found
+//     pool timeout                           -> 503 Service Unavailable
+//     anything else                          -> 500, details are only logged
+//
+// Logging goes through the `log` facade, so the crate using the macro needs
+// `log` as a dependency.
+//
+// Bulk requests that fail answer 422 with one entry per failed row in an
+// `errors` extension member.
+
+use quote::quote;
+
+pub fn api_error_definition() -> proc_macro2::TokenStream {
+    quote! {
+        /// RFC 7807 problem details returned by the generated handlers.
+        #[derive(Debug, Serialize)]
+        pub struct ApiError {
+            #[serde(rename = "type")]
+            pub kind: String,
+            pub title: String,
+            pub status: u16,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub detail: Option<String>,
+            #[serde(skip_serializing_if = "Vec::is_empty")]
+            pub errors: Vec<RowError>,
+        }
+
+        /// Why one row of a bulk request failed.
+        #[derive(Debug, Serialize)]
+        pub struct RowError {
+            /// Position of the row in the request body.
+            pub index: usize,
+            pub status: u16,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub detail: Option<String>,
+        }
+
+        impl RowError {
+            pub fn new(index: usize, error: ApiError) -> Self {
+                RowError {
+                    index,
+                    status: error.status,
+                    detail: error.detail,
+                }
+            }
+        }
+
+        impl ApiError {
+            pub fn new(status: actix_web::http::StatusCode, detail: Option<String>) -> Self {
+                ApiError {
+                    kind: String::from("about:blank"),
+                    title: status.canonical_reason().unwrap_or("Error").to_string(),
+                    status: status.as_u16(),
+                    detail,
+                    errors: Vec::new(),
+                }
+            }
+
+            /// 422 for a bulk request, listing the rows that failed.
+            pub fn rows(errors: Vec<RowError>) -> Self {
+                let detail = format!("{} row(s) failed, nothing was changed", errors.len());
+                ApiError {
+                    errors,
+                    ..ApiError::new(actix_web::http::StatusCode::UNPROCESSABLE_ENTITY, Some(detail))
+                }
+            }
+
+            pub fn not_found(detail: impl Into<String>) -> Self {
+                ApiError::new(actix_web::http::StatusCode::NOT_FOUND, Some(detail.into()))
+            }
+
+            pub fn bad_request(detail: impl Into<String>) -> Self {
+                ApiError::new(actix_web::http::StatusCode::BAD_REQUEST, Some(detail.into()))
+            }
+        }
+
+        impl std::fmt::Display for ApiError {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match &self.detail {
+                    Some(detail) => write!(f, "{}: {}", self.title, detail),
+                    None => write!(f, "{}", self.title),
+                }
+            }
+        }
+
+        impl actix_web::ResponseError for ApiError {
+            fn status_code(&self) -> actix_web::http::StatusCode {
+                actix_web::http::StatusCode::from_u16(self.status)
+                    .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR)
+            }
+
+            fn error_response(&self) -> HttpResponse {
+                HttpResponse::build(self.status_code())
+                    .content_type("application/problem+json")
+                    .json(self)
+            }
+        }
+
+        impl From<sqlx::Error> for ApiError {
+            fn from(error: sqlx::Error) -> Self {
+                use actix_web::http::StatusCode;
+                match &error {
+                    sqlx::Error::RowNotFound => ApiError::not_found("no matching row"),
+                    sqlx::Error::PoolTimedOut => {
+                        ApiError::new(StatusCode::SERVICE_UNAVAILABLE, None)
+                    }
+                    sqlx::Error::Database(db) => {
+                        let code = db.code().map(|c| c.to_string()).unwrap_or_default();
+                        let status = match code.as_str() {
+                            "23505" => StatusCode::CONFLICT,
+                            "23503" | "23502" | "23514" => StatusCode::UNPROCESSABLE_ENTITY,
+                            c if c.starts_with("22") => StatusCode::BAD_REQUEST,
+                            _ => StatusCode::INTERNAL_SERVER_ERROR,
+                        };
+                        if status == StatusCode::INTERNAL_SERVER_ERROR {
+                            log::error!("Error executing query: {:?}", error);
+                            ApiError::new(status, None)
+                        } else {
+                            ApiError::new(status, Some(db.message().to_string()))
+                        }
+                    }
+                    _ => {
+                        log::error!("Error executing query: {:?}", error);
+                        ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, None)
+                    }
+                }
+            }
+        }
+
+        // Percent-encodes a key value for use as a path segment in `Location`.
+        fn location_segment<T: Serialize>(value: &T) -> String {
+            percent_encode(&text_value(value))
+        }
+
+        // A value as text, the way it is written in a path or query string.
+        fn text_value<T: Serialize>(value: &T) -> String {
+            match serde_json::to_value(value) {
+                Ok(serde_json::Value::String(s)) => s,
+                Ok(other) => other.to_string(),
+                Err(_) => String::new(),
+            }
+        }
+
+        // Encodes everything but RFC 3986 unreserved characters.
+        fn percent_encode(text: &str) -> String {
+            let mut out = String::new();
+            for byte in text.bytes() {
+                match byte {
+                    b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                        out.push(byte as char)
+                    }
+                    _ => out.push_str(&format!("%{:02X}", byte)),
+                }
+            }
+            out
+        }
+    }
+}
@@ -0,0 +1,161 @@
+// ETags for the by-key handlers of tables with a primary key.
+//
+//     GET    /collections/{id}                    200, ETag: "3"
+//     GET    /collections/{id}  If-None-Match: "3"  304
+//     PATCH  /collections/{id}  If-Match: "2"       412, someone else got there first
+//
+// The tag is the value of a version column, `updated_at`, or a hash of the
+// serialized row; see the `etags` option in `config.rs`. An integer version
+// column is left out of client input and incremented by every update. `If-Match` is
+// checked against the row locked with `SELECT ... FOR UPDATE`, in the same
+// transaction as the change, so two editors cannot both pass the check.
+
+use crate::config::Config;
+use crate::field_ident;
+use crate::repository::Names;
+use quote::quote;
+
+/// Where a table's ETag comes from.
+pub enum Source<'a> {
+    Column(&'a parse::Column),
+    Hash,
+}
+
+const HASH: &str = "hash";
+
+/// The configured source, or `version`, then `updated_at`, then the row hash.
+pub fn source<'a>(table: &'a parse::Table, config: &Config) -> Source<'a> {
+    let column = match config.etag_source(&table.name) {
+        Some(HASH) => None,
+        Some(name) => table.column(name),
+        None => table
+            .column("version")
+            .or_else(|| table.column("updated_at")),
+    };
+    column.map_or(Source::Hash, Source::Column)
+}
+
+/// The source column when it is an integer version, which updates increment.
+pub fn version_column<'a>(table: &'a parse::Table, config: &Config) -> Option<&'a parse::Column> {
+    match source(table, config) {
+        Source::Column(column)
+            if matches!(
+                crate::types::normalize(&column.dtype).as_str(),
+                "int2" | "smallint" | "int" | "int4" | "integer" | "int8" | "bigint"
+            ) && !table.is_primary_key(&column.name) =>
+        {
+            Some(column)
+        }
+        _ => None,
+    }
+}
+
+/// Every `etags` entry must name a table and one of its columns, or `hash`.
+pub fn validate(schema: &parse::Schema, config: &Config) -> syn::Result<()> {
+    for (table, source) in &config.etags {
+        let error = |message: String| Err(syn::Error::new(proc_macro2::Span::call_site(), message));
+        let Some(found) = schema
+            .tables
+            .iter()
+            .find(|t| t.name.trim_matches('"').eq_ignore_ascii_case(table))
+        else {
+            return error(format!("etags: unknown table `{}`", table));
+        };
+        if found.primary_key().is_empty() {
+            return error(format!("etags: `{}` has no primary key", table));
+        }
+        if source != HASH && found.column(source).is_none() {
+            return error(format!(
+                "etags: `{}` has no column `{}`, expected a column or `hash`",
+                table, source
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// `Model::etag`, for tables with a primary key.
+pub fn model(table: &parse::Table, config: &Config) -> proc_macro2::TokenStream {
+    let model = Names::new(table, config).model;
+    let (doc, body) = match source(table, config) {
+        Source::Column(column) => {
+            let field = field_ident(&column.name);
+            (
+                format!("Entity tag of the row, from `{}`.", column.name),
+                quote! { format!("\"{}\"", text_value(&self.#field)) },
+            )
+        }
+        Source::Hash => (
+            String::from("Entity tag of the row, hashed from all of its fields."),
+            // 64-bit FNV-1a, so a row keeps its tag across builds and Rust
+            // versions, unlike `DefaultHasher`.
+            quote! {
+                let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+                for byte in serde_json::to_vec(self).unwrap_or_default() {
+                    hash ^= u64::from(byte);
+                    hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+                }
+                format!("\"{:016x}\"", hash)
+            },
+        ),
+    };
+    quote! {
+        impl #model {
+            #[doc = #doc]
+            pub fn etag(&self) -> String {
+                #body
+            }
+        }
+    }
+}
+
+/// `pub mod etag`, the conditional request checks shared by every table.
+pub fn support() -> proc_macro2::TokenStream {
+    quote! {
+        pub mod etag {
+            use super::ApiError;
+            use actix_web::http::header::{HeaderValue, IF_MATCH, IF_NONE_MATCH};
+
+            // `*` or a comma-separated list of tags. Weak tags (`W/"..."`) only
+            // count for `If-None-Match`.
+            fn listed(header: &HeaderValue, etag: &str, weak: bool) -> bool {
+                let Ok(header) = header.to_str() else {
+                    return false;
+                };
+                header.split(',').map(str::trim).any(|tag| {
+                    tag == "*" || tag == etag || (weak && tag.strip_prefix("W/") == Some(etag))
+                })
+            }
+
+            /// Whether the request sent `If-Match`, so the row must be locked and checked.
+            pub fn has_if_match(req: &actix_web::HttpRequest) -> bool {
+                req.headers().contains_key(IF_MATCH)
+            }
+
+            /// 412 when `If-Match` is sent and does not list `current`, the tag of
+            /// the row as it is now (`None` when there is no such row).
+            pub fn check_if_match(
+                req: &actix_web::HttpRequest,
+                current: Option<String>,
+            ) -> Result<(), ApiError> {
+                let Some(header) = req.headers().get(IF_MATCH) else {
+                    return Ok(());
+                };
+                match current {
+                    Some(etag) if listed(header, &etag, false) => Ok(()),
+                    _ => Err(ApiError::new(
+                        actix_web::http::StatusCode::PRECONDITION_FAILED,
+                        Some(String::from("the row has changed since it was read")),
+                    )),
+                }
+            }
+
+            /// Whether `If-None-Match` lists `etag`, so the client's copy is current.
+            pub fn not_modified(req: &actix_web::HttpRequest, etag: &str) -> bool {
+                req.headers()
+                    .get(IF_NONE_MATCH)
+                    .is_some_and(|header| listed(header, etag, true))
+            }
+        }
+    }
+}
@@ -1,4 +1,17 @@
 extern crate proc_macro;
+mod audit;
+mod batch;
+mod bulk;
+mod config;
+mod errors;
+mod etag;
+mod list;
+mod lookup;
+mod nested;
+mod openapi;
+mod repository;
+mod soft_delete;
+mod types;
 use parse;
 use proc_macro::TokenStream;
 use quote::quote;
@@ -188,150 +201,393 @@ pub fn add_functions_from_file(attr: TokenStream, item: TokenStream) -> TokenStr
     //println!("{}", output);
     TokenStream::from(output)
 }
+// Reads the tables from a single DDL script, a directory of migrations, or a
+// JSON snapshot written by `parse::introspect`. A table statement the parser
+// does not understand is reported at `span` rather than skipped.
+fn load_schema(
+    file_path: &PathBuf,
+    span: proc_macro2::Span,
+) -> syn::Result<(parse::Schema, Vec<PathBuf>)> {
+    let error = |e: &dyn std::fmt::Display| syn::Error::new(span, e.to_string());
+    if file_path.extension().is_some_and(|ext| ext == "json") {
+        let schema = parse::Schema::read_snapshot(file_path).map_err(|e| error(&e))?;
+        Ok((schema, vec![file_path.clone()]))
+    } else if file_path.is_dir() {
+        let files = parse::migration_files(file_path).map_err(|e| error(&e))?;
+        let schema = parse::Schema::from_migrations_dir(file_path).map_err(|e| error(&e))?;
+        Ok((schema, files))
+    } else {
+        let file_content = fs::read_to_string(file_path).map_err(|e| error(&e))?;
+        let schema = parse::Schema::from_sql(&file_content).map_err(|e| {
+            error(&parse::SchemaError {
+                file: Some(file_path.clone()),
+                ..e
+            })
+        })?;
+        Ok((schema, vec![file_path.clone()]))
+    }
+}
+
+// Two tables whose names singularise alike (or share a configured name) would
+// define every generated struct twice.
+fn check_model_names(schema: &parse::Schema, config: &config::Config) -> syn::Result<()> {
+    let mut seen: Vec<(String, &str)> = Vec::new();
+    for table in &schema.tables {
+        let model = types::model_name(&table.name, config);
+        if let Some((_, other)) = seen.iter().find(|(name, _)| *name == model) {
+            return Err(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                format!(
+                    "tables `{}` and `{}` both generate the model `{}`, name one of them in `models`",
+                    other, table.name, model
+                ),
+            ));
+        }
+        seen.push((model, &table.name));
+    }
+    Ok(())
+}
+
+// Quoted identifiers such as "memberOfCollections" keep their quotes in SQL
+// but need them stripped to become Rust identifiers. Keywords become raw
+// identifiers, `type` -> `r#type`, except the few that cannot be raw, which
+// get a trailing underscore.
+fn field_ident(name: &str) -> syn::Ident {
+    let name = json_name(name);
+    let span = proc_macro2::Span::call_site();
+    if syn::parse_str::<syn::Ident>(&name).is_ok() {
+        syn::Ident::new(&name, span)
+    } else if matches!(name.as_str(), "self" | "Self" | "super" | "crate" | "_") {
+        syn::Ident::new(&format!("{}_", name), span)
+    } else {
+        syn::Ident::new_raw(&name, span)
+    }
+}
+
+// The column's name in JSON and in query strings: `type`, not `r#type`.
+fn json_name(name: &str) -> String {
+    name.trim_matches('"').to_string()
+}
+
+// `#[serde(rename)]`, and `#[sqlx(rename)]` for the row model, when the field
+// is not spelled like the column.
+fn rename(name: &str, sqlx: bool) -> proc_macro2::TokenStream {
+    let json = json_name(name);
+    if field_ident(name) == json {
+        return quote! {};
+    }
+    if sqlx {
+        quote! {
+            #[serde(rename = #json)]
+            #[sqlx(rename = #json)]
+        }
+    } else {
+        quote! { #[serde(rename = #json)] }
+    }
+}
+
+/// Generates structs and actix handlers for every table in a DDL file, a
+/// directory of refinery/sqlx migrations replayed in version order, or a JSON
+/// schema snapshot produced by `parse::introspect`.
+///
+/// Column types can be overridden per SQL type or per `table.column` pattern,
+/// inline or in `rust-openapi.toml`; see `config.rs`.
+///
+/// Edits to the DDL, the migrations and the config file trigger a rebuild.
+/// A migration added to the directory does not; have the crate's `build.rs`
+/// print `cargo:rerun-if-changed=<migrations dir>` for that.
 #[proc_macro]
 pub fn generate_structs_from_ddl(attr: TokenStream) -> TokenStream {
-    // Parse the attribute input for the file path
-    let relative_path = parse_macro_input!(attr as syn::LitStr).value();
+    // Parse the DDL file or migrations directory, followed by any options
+    let input = parse_macro_input!(attr as config::MacroInput);
+    let relative_path = input.path.value();
+    let path_span = input.path.span();
 
     // Construct the absolute path
     let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR not set");
-    let file_path = PathBuf::from(manifest_dir).join(relative_path);
+    let manifest_dir = PathBuf::from(manifest_dir);
+    let file_path = manifest_dir.join(relative_path);
 
-    // Read the JSON file
-    let file_content = fs::read_to_string(file_path).expect("Unable to read file");
+    let config = match config::Config::load(&manifest_dir, input) {
+        Ok(config) => config,
+        Err(e) => return e.to_compile_error().into(),
+    };
 
-    let sql = file_content.split(";");
-    // Generate structs based on the JSON data
-    let mut output = quote! {};
+    let (schema, sources) = match load_schema(&file_path, path_span) {
+        Ok(loaded) => loaded,
+        Err(e) => return e.to_compile_error().into(),
+    };
 
-    for statement in sql.into_iter() {
-        let stmt = parse::create_table_parser().parse(statement);
-        if stmt.is_none() {
-            continue;
-        }
-        //let create_table_result = create_table_parser().parse("CREATE TABLE TEST(id int, id2 int)");
-        let ddl = stmt.unwrap();
-        let table_name = &ddl.0.name;
-        let struct_name = syn::Ident::new(&ddl.0.name, proc_macro2::Span::call_site());
-        let columns = ddl.0.columns;
+    // Generate structs based on the parsed tables
+    let mut output = config.type_checks();
 
-        let fields = columns.iter().map(|col| {
-            let field_name = syn::Ident::new(&col.name, proc_macro2::Span::call_site());
+    // Recompile whenever a DDL file, migration or the config file changes.
+    for source in sources.iter().chain(&config.file) {
+        let source = source.to_string_lossy().to_string();
+        output.extend(quote! {
+            const _: &str = include_str!(#source);
+        });
+    }
 
-            let field_type_str = col.dtype.to_string();
-            let field_ty: syn::Type = match field_type_str.as_str() {
-                "VARCHAR" => syn::parse_str("String").expect("Invalid type"),
-                "INT" => syn::parse_str("i32").expect("Invalid type"),
-                "BOOL" => syn::parse_str("bool").expect("Invalid type"),
-                // Handle other cases as needed
-                _ => syn::parse_str("String").expect("Invalid type"),
-            };
+    if let Err(e) = etag::validate(&schema, &config) {
+        return e.to_compile_error().into();
+    }
+    if let Err(e) = soft_delete::validate(&schema, &config) {
+        return e.to_compile_error().into();
+    }
+    if let Err(e) = check_model_names(&schema, &config) {
+        return e.to_compile_error().into();
+    }
+    if let Err(e) = types::validate(&schema, &config) {
+        return e.to_compile_error().into();
+    }
 
-            quote! {
-                pub #field_name: Option<#field_ty>,
+    output.extend(errors::api_error_definition());
+    output.extend(list::support(&config));
+    output.extend(bulk::support(&schema, &config));
+    output.extend(audit::support());
+    output.extend(types::support(&schema, &config));
+    if schema.tables.iter().any(|t| !t.primary_key().is_empty()) {
+        output.extend(etag::support());
+    }
+    // Only emitted when some patch model has a nullable field, or it is dead code.
+    let nullable_patch = schema.tables.iter().any(|table| {
+        table.columns.iter().any(|col| {
+            !table.is_primary_key(&col.name)
+                && !repository::is_managed(table, col, &config)
+                && table.is_nullable(col)
+        })
+    });
+    if nullable_patch {
+        output.extend(quote! {
+            // Deserializes a field that is present in the body, even as null, to `Some`.
+            fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+                T: Deserialize<'de>,
+            {
+                T::deserialize(deserializer).map(Some)
+            }
+        });
+    }
+
+    for enum_type in &schema.enums {
+        output.extend(types::enum_definition(enum_type));
+    }
+
+    let mut configure_fns = Vec::new();
+
+    for ddl in schema.tables.iter().cloned() {
+        let table_name = &ddl.name;
+        let names = repository::Names::new(&ddl, &config);
+        let struct_name = &names.model;
+        let new_struct_name = &names.new_model;
+        let patch_struct_name = &names.patch;
+        let repository_name = &names.repository;
+        let columns = &ddl.columns;
+
+        // Read model: what a row looks like, NOT NULL columns are not optional.
+        let fields = columns.iter().map(|col| {
+            let field_name = field_ident(&col.name);
+            let field_ty = types::column_type(table_name, col, &schema, &config);
+            let rename = rename(&col.name, true);
+            if ddl.is_nullable(col) {
+                quote! { #rename pub #field_name: Option<#field_ty>, }
+            } else {
+                quote! { #rename pub #field_name: #field_ty, }
             }
         });
 
-        let fields2 = columns.iter().map(|col| {
-            let field_name = syn::Ident::new(&col.name, proc_macro2::Span::call_site());
+        // Create model: columns the server maintains (audit and soft delete
+        // columns) are left out, the rest are required unless they are
+        // nullable or have a default.
+        let new_fields = repository::insert_columns(&ddl, &config)
+            .into_iter()
+            .map(|col| {
+                let field_name = field_ident(&col.name);
+                let field_ty = types::column_type(table_name, col, &schema, &config);
+                let rename = rename(&col.name, false);
+                if repository::is_optional(&ddl, col) {
+                    quote! {
+                        #rename
+                        #[serde(default, skip_serializing_if = "Option::is_none")]
+                        pub #field_name: Option<#field_ty>,
+                    }
+                } else {
+                    quote! { #rename pub #field_name: #field_ty, }
+                }
+            });
 
-            quote! {
-                   .bind(json.#field_name)
+        // Update model: every column but the primary key and the ones the
+        // server maintains, all optional. A nullable column is `Some(None)` when
+        // the body sets it to null.
+        let patch_columns: Vec<&parse::Column> = columns
+            .iter()
+            .filter(|col| {
+                !ddl.is_primary_key(&col.name) && !repository::is_managed(&ddl, col, &config)
+            })
+            .collect();
+        let patch_fields = patch_columns.iter().map(|col| {
+            let field_name = field_ident(&col.name);
+            let field_ty = types::column_type(table_name, col, &schema, &config);
+            let rename = rename(&col.name, false);
+            if ddl.is_nullable(col) {
+                quote! {
+                    #rename
+                    #[serde(default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+                    pub #field_name: Option<Option<#field_ty>>,
+                }
+            } else {
+                quote! {
+                    #rename
+                    #[serde(default, skip_serializing_if = "Option::is_none")]
+                    pub #field_name: Option<#field_ty>,
+                }
             }
         });
-        let fields3 = columns.iter().map(|col| {
-            let colname = &col.name;
-            let field_name = syn::Ident::new(colname, proc_macro2::Span::call_site());
+        let present_fields = patch_columns.iter().map(|col| {
+            let field_name = field_ident(&col.name);
+            let name = json_name(&col.name);
             quote! {
-                if !self.#field_name.is_none(){
-                    fields.push((#colname, &self.#field_name as &dyn std::fmt::Debug) );
+                if self.#field_name.is_some() {
+                    fields.push(#name);
                 }
             }
         });
 
-        let cols = columns
-            .iter()
-            .map(|col| col.name.clone())
-            .collect::<Vec<_>>()
-            .join(",");
-        let primary_key: Vec<parse::Column> = columns
+        let primary_key = ddl.primary_key();
+        let key_columns: Vec<&parse::Column> = primary_key
             .iter()
-            .filter(|col| !col.constraints.is_empty())
-            .map(move |col| col.clone())
+            .filter_map(|name| ddl.column(name))
             .collect();
+        let key_struct_name = &names.key;
+        let key_fields = key_columns.iter().map(|col| {
+            let field_name = field_ident(&col.name);
+            let field_ty = types::column_type(table_name, col, &schema, &config);
+            let rename = rename(&col.name, false);
+            quote! { #rename pub #field_name: #field_ty, }
+        });
 
         let new_struct = quote! {
             #[derive(Deserialize,Serialize,Debug,sqlx::FromRow)]
+            #[allow(non_snake_case)]
             pub struct #struct_name {
                 #(#fields)*
             }
 
-        };
-
-        let new_struct2 = quote! {
-                       impl #struct_name {
-
-
-               pub fn non_null_fields(&self) -> Vec<(&str, &dyn std::fmt::Debug)>{
-
-                           let mut fields = Vec::new();
-                           #(#fields3)*
-                           fields
-                       }
-        pub fn bind_fields(&self,  sqlx_query:&mut sqlx::query::Query<sqlx::Postgres, sqlx::postgres::PgArguments> ) -> (){
-
-                          // #(#fields2)*
-                       }
-
-
-
+            #[derive(Deserialize,Serialize,Debug)]
+            #[allow(non_snake_case)]
+            pub struct #new_struct_name {
+                #(#new_fields)*
+            }
 
-                       }
+            #[derive(Deserialize,Serialize,Debug,Default)]
+            #[allow(non_snake_case)]
+            pub struct #patch_struct_name {
+                #(#patch_fields)*
+            }
 
-                   };
+            /// Primary key of the table, one field per key column in key order.
+            #[derive(Deserialize,Serialize,Debug,Clone,PartialEq)]
+            #[allow(non_snake_case)]
+            pub struct #key_struct_name {
+                #(#key_fields)*
+            }
 
-        for col in primary_key {
-            let name = col.name;
-            let key: &str = &name;
-            let inner_fields = columns.iter().filter(|col| col.name != *key).map(|col| {
-                let field_name = syn::Ident::new(&col.name, proc_macro2::Span::call_site());
+        };
 
-                quote! {
-                       .bind(json.#field_name.unwrap())
+        let new_struct2 = quote! {
+            impl #patch_struct_name {
+                /// Names of the fields present in the patch.
+                pub fn present_fields(&self) -> Vec<&'static str> {
+                    let mut fields = Vec::new();
+                    #(#present_fields)*
+                    fields
                 }
-            });
+            }
+        };
 
-            let select = "SELECT ".to_owned()
-                + &cols.to_owned()
-                + " FROM "
-                + &table_name.to_owned()
-                + " WHERE "
-                + key
-                + " = $1";
-            let del =
-                "DELETE FROM ".to_owned() + &table_name.to_owned() + " WHERE " + key + " = $1";
-
-            let get = "get_".to_owned() + &table_name.to_lowercase() + "_by_" + key;
-            let route = "/".to_owned() + table_name + "/" + "{" + key + "}";
+        // Every handler emitted for this table, in registration order.
+        let mut services: Vec<syn::Ident> = Vec::new();
+
+        if !key_columns.is_empty() {
+            let key_names: Vec<syn::Ident> = key_columns
+                .iter()
+                .map(|col| field_ident(&col.name))
+                .collect();
+            // {asset_id}/{collection_id}
+            let key_path = key_names
+                .iter()
+                .map(|name| format!("{{{}}}", name))
+                .collect::<Vec<_>>()
+                .join("/");
+            let key = key_names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+                .join("_and_");
+            let get = "get_".to_owned() + &table_name.to_lowercase() + "_by_" + &key;
+            let route = "/".to_owned() + table_name + "/" + &key_path;
             let get_handler_function_name = get + "_handler";
 
             let get_handler_function_name_syn =
                 syn::Ident::new(&get_handler_function_name, proc_macro2::Span::call_site());
 
-            let key_syn = syn::Ident::new(key, proc_macro2::Span::call_site());
+            let not_found = format!("{} not found", table_name);
+            // `?include_deleted=true` finds soft deleted rows too.
+            let fetch = if soft_delete::column(&ddl, &config).is_some() {
+                quote! {
+                    let include_deleted = pairs.iter().find(|(key, _)| key == "include_deleted");
+                    let include_deleted = match include_deleted {
+                        Some((key, value)) => list_query::parse_flag(key, value)?,
+                        None => false,
+                    };
+                    let row = if include_deleted {
+                        #repository_name::get_by_pk_with_deleted(pool.get_ref(), &path).await?
+                    } else {
+                        #repository_name::get_by_pk(pool.get_ref(), &path).await?
+                    };
+                }
+            } else {
+                quote! {
+                    let row = #repository_name::get_by_pk(pool.get_ref(), &path).await?;
+                }
+            };
             let get_handler = quote! {
-            #[get(#route)]
-            async fn #get_handler_function_name_syn(path: web::Path<#struct_name>, pool: web::Data<PgPool>) -> impl Responder {
-                    println!("{}", #select);
-                    let v = path.into_inner();
-                let res: Vec<#struct_name> = sqlx::query_as::<_,#struct_name>(#select).bind(v.#key_syn).fetch_all( pool.get_ref()).await.unwrap();
-                let mut response = HttpResponse::Ok();
-                response.insert_header(("Content-Type", "application/json"));
-                response.json(res)
-            }
-                };
-            let delete = "delete_".to_owned() + &table_name.to_lowercase() + "_by_" + key;
-            let route = "/".to_owned() + table_name + "/" + "{" + key + "}";
+                #[get(#route)]
+                async fn #get_handler_function_name_syn(
+                    req: actix_web::HttpRequest,
+                    path: web::Path<#key_struct_name>,
+                    pool: web::Data<PgPool>,
+                ) -> Result<HttpResponse, ApiError> {
+                    let pairs = web::Query::<Vec<(String, String)>>::from_query(req.query_string())
+                        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+                    let expand = match pairs.iter().find(|(key, _)| key == "expand") {
+                        Some((_, value)) => list_query::parse_expand(value, &#repository_name::TABLE)?,
+                        None => Vec::new(),
+                    };
+                    #fetch
+                    let Some(row) = row else {
+                        return Err(ApiError::not_found(#not_found));
+                    };
+                    let tag = row.etag();
+                    if etag::not_modified(&req, &tag) {
+                        return Ok(HttpResponse::NotModified()
+                            .insert_header((actix_web::http::header::ETAG, tag))
+                            .finish());
+                    }
+                    let mut response = HttpResponse::Ok();
+                    response.insert_header((actix_web::http::header::ETAG, tag));
+                    if expand.is_empty() {
+                        return Ok(response.json(row));
+                    }
+                    let mut rows = [list_query::object(&row)];
+                    list_query::expand(pool.get_ref(), &mut rows, &expand).await?;
+                    let [row] = rows;
+                    Ok(response.json(row))
+                }
+            };
+            let delete = "delete_".to_owned() + &table_name.to_lowercase() + "_by_" + &key;
             let delete_handler_function_name = delete + "_handler";
 
             let delete_handler_function_name_syn = syn::Ident::new(
@@ -340,23 +596,36 @@ pub fn generate_structs_from_ddl(attr: TokenStream) -> TokenStream {
             );
 
             let delete_handler = quote! {
-            #[delete(#route)]
-            async fn #delete_handler_function_name_syn(path: web::Path<#struct_name>, pool: web::Data<PgPool>) -> impl Responder {
-                    println!("{}", #del);
-                    let v = path.into_inner();
-                let res = sqlx::query(#del).bind(v.#key_syn).execute( pool.get_ref()).await.unwrap();
-                let mut response = HttpResponse::Ok();
-                //response.insert_header(("Content-Type", "application/json"));
-                //response.json(res)
-                response
-            }
-                };
+                #[delete(#route)]
+                async fn #delete_handler_function_name_syn(
+                    req: actix_web::HttpRequest,
+                    path: web::Path<#key_struct_name>,
+                    pool: web::Data<PgPool>,
+                ) -> Result<HttpResponse, ApiError> {
+                    let mut tx = audit::begin(&pool, &req).await?;
+                    if etag::has_if_match(&req) {
+                        let current = #repository_name::lock(&mut *tx, &path).await?;
+                        etag::check_if_match(&req, current.map(|row| row.etag()))?;
+                    }
+                    if !#repository_name::delete(&mut *tx, &path).await? {
+                        return Err(ApiError::not_found(#not_found));
+                    }
+                    tx.commit().await?;
+                    Ok(HttpResponse::NoContent().finish())
+                }
+            };
 
+            output.extend(etag::model(&ddl, &config));
             output.extend(get_handler);
             output.extend(delete_handler);
+            let (restore_handlers, restore_services) =
+                soft_delete::routes(&ddl, &key_path, &config);
+            output.extend(restore_handlers);
+            services.extend(restore_services);
+            services.push(get_handler_function_name_syn);
+            services.push(delete_handler_function_name_syn);
 
             let update = "update_".to_owned() + &table_name.to_lowercase();
-            let route = "/".to_owned() + table_name + "/" + "{" + key + "}";
             let update_handler_function_name = update + "_handler";
 
             let update_handler_function_name_syn = syn::Ident::new(
@@ -365,100 +634,217 @@ pub fn generate_structs_from_ddl(attr: TokenStream) -> TokenStream {
             );
 
             let update_handler = quote! {
-                                                                            #[patch(#route)]
-                                                                            async fn #update_handler_function_name_syn(path: web::Path<#struct_name>, json: web::Json<#struct_name>, pool: web::Data<PgPool>) -> impl Responder {
-                                                                        let active_fields : Vec<(&str, &dyn std::fmt::Debug)>= json.non_null_fields();
-                                                                        let fields_length  = active_fields.len();
-                                                                let insert_sql = "UPDATE ".to_owned() + &#table_name.to_owned()
-                                                                                + " set " + &active_fields.into_iter().enumerate().filter(|(index, (name, value)) | *name!=#key ). map(|(index,(name,value)) | format!(" {} = ${} ", &name.to_string(), index+2)).collect::<Vec<_>>().join(" ").to_owned()
-                                                                                + " where "
-                                                                                 + #key + &format!(" = ${} ", fields_length).to_string();
-                        println!("{}",insert_sql);
-                                            let v= path.into_inner();
-
-                                    let mut sqlx_query: sqlx::query::Query<sqlx::Postgres, sqlx::postgres::PgArguments> = sqlx::query(&insert_sql).bind(&v.#key_syn) #(#inner_fields)*;
-                                            let result = sqlx_query.execute( pool.get_ref()).await;
-                    println!("{}", json.id2.unwrap());
-            match result { Ok(res) => { println!("Query executed successfully: {:?}", res); } Err(e) => {  println!("Error executing query: {:?}", e); } }
-
-                                                                                let mut response = HttpResponse::Ok();
-                                                                                //response.insert_header(("Content-Type", "application/json"));
-                                                                                //response.json(res)
-                                                                                response
-                                                                            }
-                                                                                };
+                #[patch(#route)]
+                async fn #update_handler_function_name_syn(
+                    req: actix_web::HttpRequest,
+                    path: web::Path<#key_struct_name>,
+                    json: web::Json<#patch_struct_name>,
+                    pool: web::Data<PgPool>,
+                ) -> Result<HttpResponse, ApiError> {
+                    let mut tx = audit::begin(&pool, &req).await?;
+                    if etag::has_if_match(&req) {
+                        let current = #repository_name::lock(&mut *tx, &path).await?;
+                        etag::check_if_match(&req, current.map(|row| row.etag()))?;
+                    }
+                    let Some(row) = #repository_name::update(&mut *tx, &path, &json).await? else {
+                        return Err(ApiError::not_found(#not_found));
+                    };
+                    tx.commit().await?;
+                    Ok(HttpResponse::Ok()
+                        .insert_header((actix_web::http::header::ETAG, row.etag()))
+                        .json(row))
+                }
+            };
             output.extend(update_handler);
-        }
-        let select = "SELECT ".to_owned() + &cols.to_owned() + " FROM " + &table_name.to_owned();
-
-        let get = "get_".to_owned() + &table_name.to_lowercase();
-
-        let getfunctionname = syn::Ident::new(&get, proc_macro2::Span::call_site());
-        let new_function = quote! {
-
-            async fn #getfunctionname (pool: &sqlx::postgres::PgPool) -> Result<Vec<#struct_name>, sqlx::Error> {
+            services.push(update_handler_function_name_syn);
 
-        let rows: Vec<#struct_name> =  sqlx::query_as::<_,#struct_name>(#select).fetch_all(pool).await?;
-
-        Ok(rows)
-
-
-                            }
-                        };
+            let upsert_handler_function_name_syn = syn::Ident::new(
+                &("upsert_".to_owned() + &table_name.to_lowercase() + "_handler"),
+                proc_macro2::Span::call_site(),
+            );
+            let upsert_handler = quote! {
+                #[actix_web::put(#route)]
+                async fn #upsert_handler_function_name_syn(
+                    req: actix_web::HttpRequest,
+                    path: web::Path<#key_struct_name>,
+                    body: web::Json<serde_json::Map<String, serde_json::Value>>,
+                    pool: web::Data<PgPool>,
+                ) -> Result<HttpResponse, ApiError> {
+                    // Key columns come from the path, whatever the body says.
+                    let mut body = body.into_inner();
+                    let key = serde_json::to_value(&*path).map_err(|e| ApiError::bad_request(e.to_string()))?;
+                    if let serde_json::Value::Object(key) = key {
+                        body.extend(key);
+                    }
+                    let new: #new_struct_name = serde_json::from_value(serde_json::Value::Object(body))
+                        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+                    let mut tx = audit::begin(&pool, &req).await?;
+                    let (row, created) = #repository_name::upsert(&mut *tx, &path, &new).await?;
+                    tx.commit().await?;
+                    if !created {
+                        return Ok(HttpResponse::Ok().json(row));
+                    }
+                    Ok(HttpResponse::Created()
+                        .insert_header((actix_web::http::header::LOCATION, req.path()))
+                        .json(row))
+                }
+            };
+            output.extend(upsert_handler);
+            services.push(upsert_handler_function_name_syn);
+        }
         let route = "/".to_owned() + table_name;
         let get_handler_function_name = "get_".to_owned() + &table_name.to_lowercase() + "_handler";
         let get_handler_function_name_syn =
             syn::Ident::new(&get_handler_function_name, proc_macro2::Span::call_site());
+        let respond = list::respond(repository_name);
         let get_handler = quote! {
-        #[get(#route)]
-        async fn #get_handler_function_name_syn(pool: web::Data<PgPool>) -> impl Responder {
-            let res: Vec<#struct_name> = #getfunctionname(pool.get_ref()).await.unwrap();
-            let mut response = HttpResponse::Ok();
-            response.insert_header(("Content-Type", "application/json"));
-            response.json(res)
-        }
-            };
+            #[get(#route)]
+            async fn #get_handler_function_name_syn(req: actix_web::HttpRequest, pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
+                let list = list_query::ListQuery::parse(req.query_string(), &#repository_name::TABLE)?;
+                #respond
+            }
+        };
 
+        // Location of the created row, e.g. /asset_collection/<asset_id>/<collection_id>.
+        let location = if key_columns.is_empty() {
+            quote! {}
+        } else {
+            let key_names = key_columns.iter().map(|col| field_ident(&col.name));
+            quote! {
+                let segments: Vec<String> = vec![#(location_segment(&row.#key_names)),*];
+                let location = format!("{}/{}", req.path().trim_end_matches('/'), segments.join("/"));
+                response.insert_header((actix_web::http::header::LOCATION, location));
+            }
+        };
         let post_handler_function_name =
             "post_".to_owned() + &table_name.to_lowercase() + "_handler";
         let post_handler_function_name_syn =
             syn::Ident::new(&post_handler_function_name, proc_macro2::Span::call_site());
-        let post_handler = quote! {
-        #[post(#route)]
-        async fn #post_handler_function_name_syn(record: web::Json<#struct_name>, pool: web::Data<PgPool>) -> impl Responder {
-
-                let json = serde_json::to_value(record).unwrap();
-                let fields: Vec<&str> = json.as_object().unwrap().keys().map(|s| s.as_str()) .collect();
-                let placeholders: Vec<String> = (1..=fields.len()).map(|i| format!("${}", i)).collect();
-                let values: Vec<&serde_json::Value> = json.as_object().unwrap().values().collect();
-                let query = format!( "INSERT INTO {} ({}) VALUES ({})", #table_name, fields.join(", "), placeholders.join(", ") );
-                let mut query_builder = sqlx::query(&query);
-                for (i, value) in values.iter().enumerate() {
-                    query_builder = match value {
-                        serde_json::Value::String(s) => query_builder.bind(s),
-                        serde_json::Value::Number(n) if n.is_i64() => query_builder.bind(n.as_i64().unwrap()),
-                        serde_json::Value::Number(n) if n.is_f64() => query_builder.bind(n.as_f64().unwrap()),
-                        serde_json::Value::Bool(b) => query_builder.bind(*b),
-                        _ => query_builder,
-                    };
+        // `?on_conflict=` turns the insert into an upsert on one of the unique keys.
+        let on_conflict: Vec<String> = repository::conflict_targets(&ddl, &config)
+            .iter()
+            .map(|(name, columns)| match name {
+                Some(name) => format!("{} ({})", name, columns.join(",")),
+                None => columns.join(","),
+            })
+            .collect();
+        let insert = if on_conflict.is_empty() {
+            let no_target = format!("{} has no unique key to resolve conflicts on", table_name);
+            quote! {
+                if conflict.is_some() {
+                    return Err(ApiError::bad_request(#no_target));
                 }
-
-                println!("{}", "Processing data");
-                query_builder.execute(pool.get_ref()).await;
-
-            let mut response = HttpResponse::Ok();
-            response.insert_header(("Content-Type", "application/json"));
-                response.json(json)
-        }
-            };
+                let row = #repository_name::insert(&mut *tx, &record).await?;
+            }
+        } else {
+            let expected = format!(
+                "unknown on_conflict, expected one of: {}",
+                on_conflict.join(", ")
+            );
+            quote! {
+                let row = match conflict {
+                    None => #repository_name::insert(&mut *tx, &record).await?,
+                    Some((_, name)) => {
+                        let target = #repository_name::conflict_target(name)
+                            .ok_or_else(|| ApiError::bad_request(#expected))?;
+                        let (row, created) = #repository_name::upsert_on(&mut *tx, &record, target).await?;
+                        if !created {
+                            tx.commit().await?;
+                            return Ok(HttpResponse::Ok().json(row));
+                        }
+                        row
+                    }
+                };
+            }
+        };
+        let post_handler = quote! {
+            #[post(#route)]
+            async fn #post_handler_function_name_syn(
+                req: actix_web::HttpRequest,
+                record: web::Json<#new_struct_name>,
+                pool: web::Data<PgPool>,
+            ) -> Result<HttpResponse, ApiError> {
+                let pairs = web::Query::<Vec<(String, String)>>::from_query(req.query_string())
+                    .map_err(|e| ApiError::bad_request(e.to_string()))?;
+                let conflict = pairs.iter().find(|(key, _)| key == "on_conflict");
+                let mut tx = audit::begin(&pool, &req).await?;
+                #insert
+                tx.commit().await?;
+                let mut response = HttpResponse::Created();
+                #location
+                Ok(response.json(row))
+            }
+        };
 
         output.extend(new_struct);
         output.extend(new_struct2);
-        output.extend(new_function);
+        output.extend(repository::repository(&ddl, &schema, &config));
         output.extend(get_handler);
         output.extend(post_handler);
+        // Collection routes go first so `/table/...` literals registered by
+        // later features are not shadowed by `/table/{key}`.
+        let (bulk_handlers, bulk_services) = bulk::routes(&ddl, &config);
+        output.extend(bulk_handlers);
+        services.splice(0..0, bulk_services);
+        let (lookup_handlers, lookup_services) = lookup::routes(&ddl, &schema, &config);
+        output.extend(lookup_handlers);
+        services.splice(0..0, lookup_services);
+        services.insert(0, post_handler_function_name_syn);
+        services.insert(0, get_handler_function_name_syn);
+
+        let (nested_handlers, nested_services) = nested::routes(&ddl, &schema, &config);
+        output.extend(nested_handlers);
+        services.extend(nested_services);
+
+        let configure_fn = syn::Ident::new(
+            &format!("configure_{}", table_name.trim_matches('"').to_lowercase()),
+            proc_macro2::Span::call_site(),
+        );
+        let doc = format!("Registers the generated `{}` routes.", table_name);
+        output.extend(quote! {
+            #[doc = #doc]
+            pub fn #configure_fn(cfg: &mut web::ServiceConfig) {
+                cfg #(.service(#services))*;
+            }
+        });
+        configure_fns.push(configure_fn);
     }
-    println!("{}", output);
+
+    // JSON and path extraction failures use the same problem details body as
+    // the handlers themselves.
+    let extractor_errors = quote! {
+        cfg.app_data(web::JsonConfig::default().error_handler(|err, _| {
+            ApiError::bad_request(err.to_string()).into()
+        }));
+        cfg.app_data(web::PathConfig::default().error_handler(|err, _| {
+            ApiError::not_found(err.to_string()).into()
+        }));
+    };
+    let configure = match config.route_prefix() {
+        Some(prefix) => quote! {
+            cfg.service(web::scope(#prefix).configure(|cfg| {
+                #extractor_errors
+                #(#configure_fns(cfg);)*
+                cfg.service(batch_handler);
+                cfg.service(openapi_handler).service(docs_handler).service(docs_asset_handler);
+            }));
+        },
+        None => quote! {
+            #extractor_errors
+            #(#configure_fns(cfg);)*
+            cfg.service(batch_handler);
+            cfg.service(openapi_handler).service(docs_handler).service(docs_asset_handler);
+        },
+    };
+    output.extend(batch::batch(&schema, &config));
+    output.extend(openapi::support(&schema, &config));
+    output.extend(quote! {
+        /// Registers every generated route, under the configured `prefix` if any:
+        /// `App::new().configure(configure)`.
+        pub fn configure(cfg: &mut web::ServiceConfig) {
+            #configure
+        }
+    });
     TokenStream::from(output)
 }
 
@@ -0,0 +1,885 @@
+// Query-string handling for the generated list endpoints.
+//
+//     GET /assets?asset_brand=ibm&created_at[gte]=2024-01-01&asset_type[in]=t1,t2
+//     GET /assets?asset_owner[is]=null&asset_name[ilike]=%25laptop%25
+//
+// Parameter names must be columns of the table and every value is bound as a
+// parameter cast to the column's type, so a bad value is a 400 from Postgres
+// (22P02) rather than anything spliced into the SQL.
+//
+// Results are paged. `limit` (default and maximum set by the `page_size` and
+// `max_page_size` options) and `offset` work on any table; tables with a
+// primary key also accept `cursor`, an opaque token naming the last row of
+// the previous page, which keeps pages stable while rows are inserted:
+//
+//     GET /assets?limit=20
+//     200 OK
+//     X-Total-Count: 134
+//     Link: </assets?limit=20&cursor=5b22...>; rel="next"
+//
+// `sort` orders by a comma-separated list of columns, `-` for descending, and
+// `fields` limits the columns selected and returned:
+//
+//     GET /assets?sort=-created_at,asset_name&fields=asset_id,asset_name
+//
+// The primary key is always appended to the sort as a tie-breaker. Cursors
+// are only offered when every sort column is NOT NULL; otherwise the next
+// page is linked by offset.
+//
+// `expand` replaces foreign key values with the rows they reference, following
+// dotted paths up to `max_expand_depth` levels:
+//
+//     GET /assets?expand=asset_brand,asset_practice.owning_brand
+//
+// Each path costs one extra query for the whole page, not one per row.
+//
+// Soft deleted rows are left out unless the request has `include_deleted=true`.
+
+use crate::config::Config;
+use crate::json_name;
+use crate::repository::Names;
+use crate::types;
+use quote::quote;
+
+/// Which operators make sense for a column.
+pub fn kind(dtype: &str, schema: &parse::Schema) -> &'static str {
+    let normalized = types::normalize(dtype);
+    if normalized.ends_with("[]") || normalized.starts_with('_') {
+        return "Opaque";
+    }
+    if schema.enum_type(&normalized).is_some() {
+        return "Ordered";
+    }
+    match normalized.as_str() {
+        "text" | "varchar" | "character varying" | "char" | "character" | "bpchar" | "name"
+        | "citext" => "Text",
+        "json" | "jsonb" | "bytea" | "xml" => "Opaque",
+        "bool" | "boolean" | "inet" | "cidr" | "macaddr" => "Plain",
+        "int2" | "smallint" | "int" | "int4" | "integer" | "int8" | "bigint" | "oid" => "Ordered",
+        "smallserial" | "serial2" | "serial" | "serial4" | "bigserial" | "serial8" => "Ordered",
+        "float4" | "real" | "float8" | "float" | "double precision" => "Ordered",
+        "numeric" | "decimal" | "uuid" | "date" | "time" | "time without time zone" => "Ordered",
+        "timestamp" | "timestamp without time zone" => "Ordered",
+        "timestamptz" | "timestamp with time zone" => "Ordered",
+        "timetz" | "time with time zone" | "interval" | "money" => "Ordered",
+        _ => "Plain",
+    }
+}
+
+// Type the bound text value is cast to. Length modifiers are dropped so a
+// long value is not silently truncated before it is compared.
+pub fn cast_type(dtype: &str, schema: &parse::Schema) -> String {
+    let normalized = types::normalize(dtype);
+    if let Some(enum_type) = schema.enum_type(&normalized) {
+        return enum_type.name.clone();
+    }
+    match normalized.as_str() {
+        "smallserial" | "serial2" => "int2".to_string(),
+        "serial" | "serial4" => "int4".to_string(),
+        "bigserial" | "serial8" => "int8".to_string(),
+        _ => normalized,
+    }
+}
+
+/// `COLUMNS` and `TABLE` consts for a table's repository.
+pub fn table(
+    table: &parse::Table,
+    schema: &parse::Schema,
+    config: &Config,
+) -> proc_macro2::TokenStream {
+    let columns = table.columns.iter().map(|col| {
+        let name = json_name(&col.name);
+        let sql = &col.name;
+        let cast = cast_type(&col.dtype, schema);
+        let kind = syn::Ident::new(kind(&col.dtype, schema), proc_macro2::Span::call_site());
+        let nullable = table.is_nullable(col);
+        quote! {
+            list_query::Column {
+                name: #name,
+                sql: #sql,
+                cast: #cast,
+                kind: list_query::Kind::#kind,
+                nullable: #nullable,
+            }
+        }
+    });
+    let sql = &table.name;
+    let deleted = match crate::soft_delete::column(table, config) {
+        Some(column) => {
+            let condition = format!("{}.{} IS NULL", table.name, column.name);
+            quote! { Some(#condition) }
+        }
+        None => quote! { None },
+    };
+    let primary_key = table.primary_key();
+    let key = primary_key.iter().map(|name| json_name(name));
+    let references = crate::nested::references(table, schema)
+        .into_iter()
+        .map(|reference| {
+            let name = json_name(&reference.column.name);
+            let parent = Names::new(reference.parent, config).repository;
+            quote! {
+                list_query::Reference {
+                    name: #name,
+                    table: #parent::table,
+                    fetch: #parent::fetch_by_key,
+                }
+            }
+        });
+    // Lets other tables expand references to this one.
+    let fetch_by_key = match primary_key.as_slice() {
+        [key] => {
+            let column = table.column(key).expect("primary key column");
+            let select = format!(
+                "SELECT {} FROM {} WHERE {} = ANY(CAST($1 AS {}[]))",
+                crate::repository::column_list(table),
+                table.name,
+                column.name,
+                cast_type(&column.dtype, schema)
+            );
+            quote! {
+                /// Rows whose primary key is one of `keys`, as JSON objects.
+                pub fn fetch_by_key(
+                    pool: &sqlx::PgPool,
+                    keys: Vec<String>,
+                ) -> list_query::BoxFuture<'_, Result<Vec<list_query::Object>, sqlx::Error>> {
+                    Box::pin(async move {
+                        let columns: Vec<&'static list_query::Column> = Self::COLUMNS.iter().collect();
+                        let rows = sqlx::query(#select).bind(keys).fetch_all(pool).await?;
+                        rows.iter().map(|row| Self::row_fields(row, &columns)).collect()
+                    })
+                }
+            }
+        }
+        _ => quote! {},
+    };
+    quote! {
+        /// Columns that list queries may filter, sort and select.
+        pub const COLUMNS: &'static [list_query::Column] = &[#(#columns),*];
+
+        pub const TABLE: list_query::Table = list_query::Table {
+            sql: #sql,
+            columns: Self::COLUMNS,
+            key: &[#(#key),*],
+            references: &[#(#references),*],
+            deleted: #deleted,
+        };
+
+        pub fn table() -> &'static list_query::Table {
+            &Self::TABLE
+        }
+
+        #fetch_by_key
+    }
+}
+
+/// The `list_query` module shared by every generated table.
+pub fn support(config: &Config) -> proc_macro2::TokenStream {
+    let page_size = i64::from(config.page_size());
+    let max_page_size = i64::from(config.max_page_size());
+    let max_expand_depth = config.max_expand_depth() as usize;
+    quote! {
+        /// Filters and paging for the generated list endpoints, parsed from the
+        /// query string.
+        pub mod list_query {
+            use super::ApiError;
+
+            /// Rows returned when a request has no `limit`.
+            pub const PAGE_SIZE: i64 = #page_size;
+            /// Larger `limit` values are clamped to this.
+            pub const MAX_PAGE_SIZE: i64 = #max_page_size;
+            /// Longest `expand` path, in foreign keys followed.
+            pub const MAX_EXPAND_DEPTH: usize = #max_expand_depth;
+
+            pub type BoxFuture<'a, T> =
+                std::pin::Pin<Box<dyn std::future::Future<Output = T> + Send + 'a>>;
+            /// A row as a JSON object, for sparse fieldsets and expansion.
+            pub type Object = serde_json::Map<String, serde_json::Value>;
+            /// Fetches rows of a table by primary key value.
+            pub type Fetch = for<'a> fn(
+                &'a sqlx::PgPool,
+                Vec<String>,
+            ) -> BoxFuture<'a, Result<Vec<Object>, sqlx::Error>>;
+
+            #[derive(Debug, Clone, Copy, PartialEq)]
+            pub enum Kind {
+                /// Any operator, including `like` and `ilike`.
+                Text,
+                /// Equality, `in`, `is` and comparisons.
+                Ordered,
+                /// Equality, `in` and `is`.
+                Plain,
+                /// Only `is` (arrays, json, bytea); cannot be sorted on.
+                Opaque,
+            }
+
+            #[derive(Debug)]
+            pub struct Column {
+                /// Query parameter and JSON field name.
+                pub name: &'static str,
+                /// Column as written in SQL.
+                pub sql: &'static str,
+                /// SQL type the bound value is cast to.
+                pub cast: &'static str,
+                pub kind: Kind,
+                pub nullable: bool,
+            }
+
+            #[derive(Debug)]
+            pub struct Table {
+                /// Table as written in SQL.
+                pub sql: &'static str,
+                pub columns: &'static [Column],
+                /// Primary key column names, which order pages and make up cursors.
+                pub key: &'static [&'static str],
+                /// Foreign keys that `expand` can follow.
+                pub references: &'static [Reference],
+                /// Condition leaving out soft deleted rows, for soft deleted tables.
+                pub deleted: Option<&'static str>,
+            }
+
+            /// A single-column foreign key onto another table's primary key.
+            #[derive(Debug)]
+            pub struct Reference {
+                /// The foreign key field.
+                pub name: &'static str,
+                pub table: fn() -> &'static Table,
+                pub fetch: Fetch,
+            }
+
+            /// A foreign key to expand, and what to expand in the rows it references.
+            #[derive(Debug)]
+            pub struct Expand {
+                pub reference: &'static Reference,
+                pub children: Vec<Expand>,
+            }
+
+            impl Table {
+                pub fn column(&self, name: &str) -> Option<&'static Column> {
+                    self.columns.iter().find(|c| c.name == name)
+                }
+            }
+
+            #[derive(Debug, Clone, Copy, PartialEq)]
+            pub enum Op {
+                Eq,
+                Ne,
+                Gt,
+                Gte,
+                Lt,
+                Lte,
+                In,
+                IsNull,
+                IsNotNull,
+                Like,
+                ILike,
+            }
+
+            impl Op {
+                fn allowed(self, kind: Kind) -> bool {
+                    match self {
+                        Op::IsNull | Op::IsNotNull => true,
+                        Op::Eq | Op::Ne | Op::In => kind != Kind::Opaque,
+                        Op::Gt | Op::Gte | Op::Lt | Op::Lte => {
+                            matches!(kind, Kind::Text | Kind::Ordered)
+                        }
+                        Op::Like | Op::ILike => kind == Kind::Text,
+                    }
+                }
+
+                fn sql(self) -> &'static str {
+                    match self {
+                        Op::Eq => " = ",
+                        Op::Ne => " <> ",
+                        Op::Gt => " > ",
+                        Op::Gte => " >= ",
+                        Op::Lt => " < ",
+                        Op::Lte => " <= ",
+                        Op::Like => " LIKE ",
+                        Op::ILike => " ILIKE ",
+                        Op::In => " = ANY",
+                        Op::IsNull => " IS NULL",
+                        Op::IsNotNull => " IS NOT NULL",
+                    }
+                }
+            }
+
+            #[derive(Debug)]
+            pub struct Filter {
+                pub column: &'static Column,
+                pub op: Op,
+                pub values: Vec<String>,
+            }
+
+            /// A condition added by a nested route rather than the query string:
+            /// `before`, then the value cast to `cast`, then `after`.
+            #[derive(Debug)]
+            pub struct Scope {
+                pub before: &'static str,
+                pub cast: &'static str,
+                pub after: &'static str,
+                pub value: String,
+            }
+
+            #[derive(Debug, Clone, Copy)]
+            pub struct Sort {
+                pub column: &'static Column,
+                pub descending: bool,
+            }
+
+            #[derive(Debug)]
+            pub struct ListQuery {
+                pub table: &'static Table,
+                pub scopes: Vec<Scope>,
+                pub filters: Vec<Filter>,
+                /// Whether soft deleted rows are listed too.
+                pub include_deleted: bool,
+                /// Requested sort followed by the primary key columns.
+                pub order: Vec<Sort>,
+                /// Columns to return, all of them when `None`.
+                pub fields: Option<Vec<&'static Column>>,
+                pub expand: Vec<Expand>,
+                pub limit: i64,
+                pub offset: i64,
+                /// Values of `order` in the last row of the previous page.
+                pub cursor: Option<Vec<String>>,
+            }
+
+            /// One page of a list query.
+            #[derive(Debug)]
+            pub struct Page<T> {
+                pub items: Vec<T>,
+                /// Whether more rows follow this page.
+                pub has_more: bool,
+                /// Cursor for the next page, when it can be paged by cursor.
+                pub next: Option<String>,
+            }
+
+            const RESERVED: &[&str] = &["limit", "offset", "cursor", "sort", "fields", "expand"];
+
+            fn column_names(columns: &[Column]) -> String {
+                columns.iter().map(|c| c.name).collect::<Vec<_>>().join(", ")
+            }
+
+            // Resolves a comma-separated list of column names from `sort` or `fields`.
+            fn column_list(
+                key: &str,
+                value: &str,
+                table: &'static Table,
+            ) -> Result<Vec<&'static Column>, ApiError> {
+                value
+                    .split(',')
+                    .map(|name| {
+                        table.column(name).ok_or_else(|| {
+                            ApiError::bad_request(format!(
+                                "unknown column `{}` in `{}`, expected one of: {}",
+                                name,
+                                key,
+                                column_names(table.columns)
+                            ))
+                        })
+                    })
+                    .collect()
+            }
+
+            fn parse_count(key: &str, value: &str, min: i64) -> Result<i64, ApiError> {
+                match value.parse::<i64>() {
+                    Ok(n) if n >= min => Ok(n),
+                    _ => Err(ApiError::bad_request(format!(
+                        "`{}` must be an integer of at least {}",
+                        key, min
+                    ))),
+                }
+            }
+
+            /// `true` or `false`.
+            pub fn parse_flag(key: &str, value: &str) -> Result<bool, ApiError> {
+                match value {
+                    "true" => Ok(true),
+                    "false" => Ok(false),
+                    _ => Err(ApiError::bad_request(format!("`{}` expects `true` or `false`", key))),
+                }
+            }
+
+            fn hex_encode(bytes: &[u8]) -> String {
+                bytes.iter().map(|b| format!("{:02x}", b)).collect()
+            }
+
+            fn hex_decode(text: &str) -> Option<Vec<u8>> {
+                if text.len() % 2 != 0 {
+                    return None;
+                }
+                (0..text.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+                    .collect()
+            }
+
+            /// Parses `expand`: comma-separated paths of foreign key fields,
+            /// dotted to continue into the referenced table.
+            pub fn parse_expand(value: &str, table: &'static Table) -> Result<Vec<Expand>, ApiError> {
+                let mut expand: Vec<Expand> = Vec::new();
+                for path in value.split(',') {
+                    let names: Vec<&str> = path.split('.').collect();
+                    if names.len() > MAX_EXPAND_DEPTH {
+                        return Err(ApiError::bad_request(format!(
+                            "`expand={}` is nested deeper than {} levels",
+                            path, MAX_EXPAND_DEPTH
+                        )));
+                    }
+                    let mut level = &mut expand;
+                    let mut current = table;
+                    for name in names {
+                        let references = current.references;
+                        let reference = references.iter().find(|r| r.name == name).ok_or_else(|| {
+                            let names: Vec<&str> = references.iter().map(|r| r.name).collect();
+                            ApiError::bad_request(format!(
+                                "cannot expand `{}` on `{}`, expected one of: {}",
+                                name,
+                                current.sql,
+                                names.join(", ")
+                            ))
+                        })?;
+                        let index = match level.iter().position(|e| e.reference.name == name) {
+                            Some(index) => index,
+                            None => {
+                                level.push(Expand { reference, children: Vec::new() });
+                                level.len() - 1
+                            }
+                        };
+                        current = (reference.table)();
+                        level = &mut level[index].children;
+                    }
+                }
+                Ok(expand)
+            }
+
+            /// Replaces the expanded foreign key values in `rows` with the rows
+            /// they reference (null if there is none), one query per foreign key.
+            pub fn expand<'a>(
+                pool: &'a sqlx::PgPool,
+                rows: &'a mut [Object],
+                expand: &'a [Expand],
+            ) -> BoxFuture<'a, Result<(), sqlx::Error>> {
+                Box::pin(async move {
+                    for e in expand {
+                        let name = e.reference.name;
+                        let mut keys: Vec<String> =
+                            rows.iter().filter_map(|row| key_text(row.get(name)?)).collect();
+                        keys.sort();
+                        keys.dedup();
+                        if keys.is_empty() {
+                            continue;
+                        }
+                        let mut parents = (e.reference.fetch)(pool, keys).await?;
+                        self::expand(pool, &mut parents, &e.children).await?;
+                        let key = (e.reference.table)().key[0];
+                        let parents: std::collections::HashMap<String, Object> = parents
+                            .into_iter()
+                            .filter_map(|parent| Some((key_text(parent.get(key)?)?, parent)))
+                            .collect();
+                        for row in rows.iter_mut() {
+                            if let Some(text) = row.get(name).and_then(key_text) {
+                                let parent = parents.get(&text).cloned();
+                                let value = parent.map(serde_json::Value::Object).unwrap_or_default();
+                                row.insert(name.to_string(), value);
+                            }
+                        }
+                    }
+                    Ok(())
+                })
+            }
+
+            /// A serialized row as a JSON object.
+            pub fn object<T: serde::Serialize>(row: &T) -> Object {
+                match serde_json::to_value(row) {
+                    Ok(serde_json::Value::Object(object)) => object,
+                    _ => Object::new(),
+                }
+            }
+
+            fn key_text(value: &serde_json::Value) -> Option<String> {
+                match value {
+                    serde_json::Value::Null => None,
+                    serde_json::Value::String(s) => Some(s.clone()),
+                    other => Some(other.to_string()),
+                }
+            }
+
+            // CAST($n AS type) for a bound text value.
+            fn push_value(
+                query: &mut sqlx::QueryBuilder<'_, sqlx::Postgres>,
+                cast: &str,
+                value: &str,
+            ) {
+                query.push("CAST(");
+                query.push_bind(value.to_string());
+                query.push(format!(" AS {})", cast));
+            }
+
+            impl ListQuery {
+                /// Parses `column=value` and `column[op]=value` pairs, where `op` is
+                /// one of `eq`, `ne`, `gt`, `gte`, `lt`, `lte`, `in` (comma
+                /// separated), `is` (`null` or `not_null`), `like` and `ilike`,
+                /// plus `sort`, `fields`, `expand`, `include_deleted` on soft deleted
+                /// tables and the paging parameters `limit`, `offset` and `cursor`.
+                pub fn parse(query: &str, table: &'static Table) -> Result<Self, ApiError> {
+                    let pairs = actix_web::web::Query::<Vec<(String, String)>>::from_query(query)
+                        .map_err(|e| ApiError::bad_request(e.to_string()))?
+                        .into_inner();
+                    let columns = table.columns;
+                    let mut list = ListQuery {
+                        table,
+                        scopes: Vec::new(),
+                        filters: Vec::new(),
+                        include_deleted: false,
+                        order: Vec::new(),
+                        fields: None,
+                        expand: Vec::new(),
+                        limit: PAGE_SIZE,
+                        offset: 0,
+                        cursor: None,
+                    };
+                    for (key, value) in pairs {
+                        match key.as_str() {
+                            "limit" => {
+                                list.limit = parse_count(&key, &value, 1)?.min(MAX_PAGE_SIZE);
+                                continue;
+                            }
+                            "offset" => {
+                                list.offset = parse_count(&key, &value, 0)?;
+                                continue;
+                            }
+                            "cursor" => {
+                                let cursor = hex_decode(&value)
+                                    .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+                                    .ok_or_else(|| ApiError::bad_request("invalid `cursor`"))?;
+                                list.cursor = Some(cursor);
+                                continue;
+                            }
+                            "sort" => {
+                                for name in value.split(',') {
+                                    let descending = name.starts_with('-');
+                                    let name = name.trim_start_matches('-');
+                                    let column = column_list(&key, name, table)?[0];
+                                    if column.kind == Kind::Opaque {
+                                        return Err(ApiError::bad_request(format!(
+                                            "cannot sort on column `{}`",
+                                            column.name
+                                        )));
+                                    }
+                                    if !list.order.iter().any(|s| s.column.name == column.name) {
+                                        list.order.push(Sort { column, descending });
+                                    }
+                                }
+                                continue;
+                            }
+                            "fields" => {
+                                list.fields = Some(column_list(&key, &value, table)?);
+                                continue;
+                            }
+                            "expand" => {
+                                list.expand = parse_expand(&value, table)?;
+                                continue;
+                            }
+                            "include_deleted" if table.deleted.is_some() => {
+                                list.include_deleted = parse_flag(&key, &value)?;
+                                continue;
+                            }
+                            _ => {}
+                        }
+                        let (name, op) = match key.strip_suffix(']').and_then(|k| k.split_once('[')) {
+                            Some((name, op)) => (name, op),
+                            None => (key.as_str(), "eq"),
+                        };
+                        let column = columns.iter().find(|c| c.name == name).ok_or_else(|| {
+                            ApiError::bad_request(format!(
+                                "unknown query parameter `{}`, expected one of: {}, {}",
+                                key,
+                                column_names(columns),
+                                RESERVED.join(", ")
+                            ))
+                        })?;
+                        let op = match (op, value.as_str()) {
+                            ("eq", _) => Op::Eq,
+                            ("ne", _) => Op::Ne,
+                            ("gt", _) => Op::Gt,
+                            ("gte", _) => Op::Gte,
+                            ("lt", _) => Op::Lt,
+                            ("lte", _) => Op::Lte,
+                            ("in", _) => Op::In,
+                            ("is", "null") => Op::IsNull,
+                            ("is", "not_null") => Op::IsNotNull,
+                            ("is", _) => {
+                                return Err(ApiError::bad_request(format!(
+                                    "`{}` expects `null` or `not_null`",
+                                    key
+                                )))
+                            }
+                            ("like", _) => Op::Like,
+                            ("ilike", _) => Op::ILike,
+                            _ => {
+                                return Err(ApiError::bad_request(format!(
+                                    "unknown operator `{}`, expected one of: eq, ne, gt, gte, lt, lte, in, is, like, ilike",
+                                    op
+                                )))
+                            }
+                        };
+                        if !op.allowed(column.kind) {
+                            return Err(ApiError::bad_request(format!(
+                                "`{}` cannot be used on column `{}`",
+                                key, column.name
+                            )));
+                        }
+                        let values = match op {
+                            Op::In => value.split(',').map(String::from).collect(),
+                            Op::IsNull | Op::IsNotNull => Vec::new(),
+                            _ => vec![value],
+                        };
+                        list.filters.push(Filter { column, op, values });
+                    }
+                    for name in table.key {
+                        let column = table.column(name).expect("key column");
+                        if !list.order.iter().any(|s| s.column.name == column.name) {
+                            list.order.push(Sort { column, descending: false });
+                        }
+                    }
+                    if let Some(cursor) = &list.cursor {
+                        if !list.keyset() {
+                            return Err(ApiError::bad_request(format!(
+                                "`cursor` is not supported for this sort on `{}`, use `offset`",
+                                table.sql
+                            )));
+                        }
+                        if cursor.len() != list.order.len() {
+                            return Err(ApiError::bad_request("invalid `cursor`"));
+                        }
+                        if list.offset != 0 {
+                            return Err(ApiError::bad_request(
+                                "`cursor` and `offset` cannot be combined",
+                            ));
+                        }
+                    }
+                    Ok(list)
+                }
+
+                // Whether pages can be fetched by cursor: the order must be
+                // total (it includes the primary key) and free of NULLs.
+                fn keyset(&self) -> bool {
+                    !self.table.key.is_empty() && self.order.iter().all(|s| !s.column.nullable)
+                }
+
+                /// Columns to select: `fields` plus whatever the order needs for
+                /// the cursor and the expanded foreign keys, or every column.
+                pub fn selected(&self) -> Vec<&'static Column> {
+                    match &self.fields {
+                        None => self.table.columns.iter().collect(),
+                        Some(fields) => {
+                            let mut selected = fields.clone();
+                            let expanded = self
+                                .expand
+                                .iter()
+                                .filter_map(|e| self.table.column(e.reference.name));
+                            let needed = self.order.iter().map(|s| s.column).chain(expanded);
+                            for column in needed {
+                                if !selected.iter().any(|c| c.name == column.name) {
+                                    selected.push(column);
+                                }
+                            }
+                            selected
+                        }
+                    }
+                }
+
+                /// Whether `name` is returned: it is in `fields` (if given) or expanded.
+                pub fn returns(&self, name: &str) -> bool {
+                    match &self.fields {
+                        None => true,
+                        Some(fields) => {
+                            fields.iter().any(|c| c.name == name)
+                                || self.expand.iter().any(|e| e.reference.name == name)
+                        }
+                    }
+                }
+
+                /// Appends `SELECT <selected columns> FROM <table>`.
+                pub fn push_select(&self, query: &mut sqlx::QueryBuilder<'_, sqlx::Postgres>) {
+                    let columns: Vec<&str> = self.selected().iter().map(|c| c.sql).collect();
+                    query.push(format!("SELECT {} FROM {}", columns.join(", "), self.table.sql));
+                }
+
+                /// Turns the rows fetched with [`push_page`](Self::push_page) into a
+                /// page, dropping the extra row.
+                pub fn page<T: serde::Serialize>(&self, mut items: Vec<T>) -> Page<T> {
+                    let has_more = items.len() as i64 > self.limit;
+                    items.truncate(self.limit as usize);
+                    let next = match items.last() {
+                        Some(last) if has_more => self.cursor_for(last),
+                        _ => None,
+                    };
+                    Page { items, has_more, next }
+                }
+
+                // Token for the page after `last`, if the rows can be paged by cursor.
+                fn cursor_for<T: serde::Serialize>(&self, last: &T) -> Option<String> {
+                    if !self.keyset() {
+                        return None;
+                    }
+                    let row = serde_json::to_value(last).ok()?;
+                    let values: Vec<String> = self
+                        .order
+                        .iter()
+                        .map(|sort| match row.get(sort.column.name)? {
+                            serde_json::Value::String(s) => Some(s.clone()),
+                            serde_json::Value::Null => None,
+                            other => Some(other.to_string()),
+                        })
+                        .collect::<Option<_>>()?;
+                    Some(hex_encode(serde_json::to_string(&values).ok()?.as_bytes()))
+                }
+
+                /// `query` with its paging parameters moved on to the next page: by
+                /// cursor when the request did not use `offset`.
+                pub fn next_query(&self, query: &str, cursor: Option<&str>) -> String {
+                    let pairs = actix_web::web::Query::<Vec<(String, String)>>::from_query(query)
+                        .map(|q| q.into_inner())
+                        .unwrap_or_default();
+                    let mut next: Vec<String> = pairs
+                        .iter()
+                        .filter(|(key, _)| !matches!(key.as_str(), "limit" | "offset" | "cursor"))
+                        .map(|(key, value)| {
+                            format!("{}={}", super::percent_encode(key), super::percent_encode(value))
+                        })
+                        .collect();
+                    next.push(format!("limit={}", self.limit));
+                    match cursor {
+                        Some(cursor) if self.offset == 0 => next.push(format!("cursor={}", cursor)),
+                        _ => next.push(format!("offset={}", self.offset + self.limit)),
+                    }
+                    next.join("&")
+                }
+
+                /// 200 with the page as a JSON array, `X-Total-Count` and a
+                /// `Link` to the next page if there is one.
+                pub fn response<T: serde::Serialize>(
+                    &self,
+                    req: &actix_web::HttpRequest,
+                    total: i64,
+                    page: Page<T>,
+                ) -> actix_web::HttpResponse {
+                    let mut response = actix_web::HttpResponse::Ok();
+                    response.insert_header(("X-Total-Count", total.to_string()));
+                    if page.has_more {
+                        let next = self.next_query(req.query_string(), page.next.as_deref());
+                        let link = format!("<{}?{}>; rel=\"next\"", req.path(), next);
+                        response.insert_header((actix_web::http::header::LINK, link));
+                    }
+                    response.json(page.items)
+                }
+
+                // The condition hiding soft deleted rows, unless they are included.
+                fn deleted(&self) -> Option<&'static str> {
+                    self.table.deleted.filter(|_| !self.include_deleted)
+                }
+
+                // Whether `push_where` appends a `WHERE`.
+                fn has_where(&self) -> bool {
+                    !self.scopes.is_empty() || !self.filters.is_empty() || self.deleted().is_some()
+                }
+
+                /// Appends ` WHERE ...` for the scopes and filters, binding every
+                /// value, and leaving out soft deleted rows.
+                pub fn push_where(&self, query: &mut sqlx::QueryBuilder<'_, sqlx::Postgres>) {
+                    let mut keyword = " WHERE ";
+                    if let Some(deleted) = self.deleted() {
+                        query.push(keyword);
+                        query.push(deleted);
+                        keyword = " AND ";
+                    }
+                    for scope in &self.scopes {
+                        query.push(keyword);
+                        query.push(scope.before);
+                        push_value(query, scope.cast, &scope.value);
+                        query.push(scope.after);
+                        keyword = " AND ";
+                    }
+                    for filter in &self.filters {
+                        query.push(keyword);
+                        keyword = " AND ";
+                        query.push(filter.column.sql);
+                        query.push(filter.op.sql());
+                        match filter.op {
+                            Op::IsNull | Op::IsNotNull => {}
+                            Op::In => {
+                                query.push("(CAST(");
+                                query.push_bind(filter.values.clone());
+                                query.push(format!(" AS {}[]))", filter.column.cast));
+                            }
+                            Op::Like | Op::ILike => {
+                                query.push_bind(filter.values[0].clone());
+                            }
+                            _ => push_value(query, filter.column.cast, &filter.values[0]),
+                        }
+                    }
+                }
+
+                /// Appends the cursor condition, `ORDER BY`, `LIMIT` and `OFFSET`
+                /// after [`push_where`](Self::push_where). One row more than the
+                /// limit is selected, to tell whether another page follows.
+                pub fn push_page(&self, query: &mut sqlx::QueryBuilder<'_, sqlx::Postgres>) {
+                    if let Some(cursor) = &self.cursor {
+                        // (a > $1) OR (a = $1 AND b > $2) ..., `<` for descending columns
+                        query.push(if self.has_where() { " AND (" } else { " WHERE (" });
+                        for i in 0..self.order.len() {
+                            query.push(if i == 0 { "(" } else { " OR (" });
+                            for (j, sort) in self.order[..=i].iter().enumerate() {
+                                if j > 0 {
+                                    query.push(" AND ");
+                                }
+                                query.push(sort.column.sql);
+                                query.push(match (j == i, sort.descending) {
+                                    (false, _) => " = ",
+                                    (true, false) => " > ",
+                                    (true, true) => " < ",
+                                });
+                                push_value(query, sort.column.cast, &cursor[j]);
+                            }
+                            query.push(")");
+                        }
+                        query.push(")");
+                    }
+                    if !self.order.is_empty() {
+                        let order: Vec<String> = self
+                            .order
+                            .iter()
+                            .map(|s| match s.descending {
+                                true => format!("{} DESC", s.column.sql),
+                                false => s.column.sql.to_string(),
+                            })
+                            .collect();
+                        query.push(format!(" ORDER BY {}", order.join(", ")));
+                    }
+                    query.push(" LIMIT ");
+                    query.push_bind(self.limit + 1);
+                    query.push(" OFFSET ");
+                    query.push_bind(self.offset);
+                }
+            }
+        }
+    }
+}
+
+/// Body of a list handler once `list` is parsed: count, fetch the page (only
+/// the requested fields if any), expand foreign keys and respond.
+pub fn respond(repository: &syn::Ident) -> proc_macro2::TokenStream {
+    quote! {
+        let total = #repository::count(pool.get_ref(), &list).await?;
+        if list.fields.is_some() || !list.expand.is_empty() {
+            let mut page = #repository::search_fields(pool.get_ref(), &list).await?;
+            list_query::expand(pool.get_ref(), &mut page.items, &list.expand).await?;
+            return Ok(list.response(&req, total, page));
+        }
+        let page = #repository::search(pool.get_ref(), &list).await?;
+        Ok(list.response(&req, total, page))
+    }
+}
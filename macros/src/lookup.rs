@@ -0,0 +1,56 @@
+// Lookups by unique key other than the primary key:
+//
+//     GET /assets/by_asset_name/{asset_name}
+//     GET /asset_collection/by_collection_id_and_position/{collection_id}/{position}
+//
+// answer the single matching row or a 404, through the repository's
+// `find_by_<columns>`.
+
+use crate::config::Config;
+use crate::repository::{self, Names};
+use crate::{field_ident, types};
+use quote::quote;
+
+/// Lookup handlers for `table`, and their names in registration order.
+pub fn routes(
+    table: &parse::Table,
+    schema: &parse::Schema,
+    config: &Config,
+) -> (proc_macro2::TokenStream, Vec<syn::Ident>) {
+    let mut output = proc_macro2::TokenStream::new();
+    let mut services = Vec::new();
+    let repository_name = Names::new(table, config).repository;
+    let lower = table.name.trim_matches('"').to_lowercase();
+    let not_found = format!("{} not found", table.name);
+
+    for columns in repository::lookups(table) {
+        let name = repository::lookup_name(&columns);
+        let fields: Vec<syn::Ident> = columns.iter().map(|c| field_ident(&c.name)).collect();
+        let tys = columns
+            .iter()
+            .map(|col| types::column_type(&table.name, col, schema, config));
+        let segments: Vec<String> = fields.iter().map(|f| format!("{{{}}}", f)).collect();
+        let route = format!("/{}/by_{}/{}", table.name, name, segments.join("/"));
+        let finder = syn::Ident::new(&format!("find_by_{}", name), proc_macro2::Span::call_site());
+        let handler = syn::Ident::new(
+            &format!("get_{}_by_{}_handler", lower, name),
+            proc_macro2::Span::call_site(),
+        );
+        output.extend(quote! {
+            #[get(#route)]
+            async fn #handler(
+                path: web::Path<(#(#tys,)*)>,
+                pool: web::Data<PgPool>,
+            ) -> Result<HttpResponse, ApiError> {
+                let (#(#fields,)*) = path.into_inner();
+                match #repository_name::#finder(pool.get_ref(), #(&#fields),*).await? {
+                    Some(row) => Ok(HttpResponse::Ok().json(row)),
+                    None => Err(ApiError::not_found(#not_found)),
+                }
+            }
+        });
+        services.push(handler);
+    }
+
+    (output, services)
+}
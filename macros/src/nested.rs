@@ -0,0 +1,333 @@
+// Nested routes derived from foreign keys.
+//
+// A single-column foreign key onto another table's primary key, e.g.
+// `products.owning_practice -> practices.practice_id`, gives
+//
+//     GET  /practices/{practice_id}/products   list, with the usual query string
+//     POST /practices/{practice_id}/products   create, `owning_practice` from the path
+//
+// and a join table, whose primary key is made of two such foreign keys like
+// `asset_collection (asset_id, collection_id)`, links the two tables directly
+// in both directions:
+//
+//     GET    /collections/{collection_id}/assets
+//     PUT    /collections/{collection_id}/assets/{asset_id}   link
+//     DELETE /collections/{collection_id}/assets/{asset_id}   unlink
+//
+// A table with several foreign keys onto the same parent gets no nested
+// routes for that parent, as they could not be told apart.
+
+use crate::config::Config;
+use crate::repository::Names;
+use crate::{field_ident, json_name, list, types};
+use quote::quote;
+
+/// A single-column foreign key onto a single-column primary key.
+pub struct Reference<'a> {
+    pub column: &'a parse::Column,
+    pub parent: &'a parse::Table,
+    pub target: &'a parse::Column,
+}
+
+/// The foreign keys of `table` that nested routes and `?expand=` can follow.
+pub fn references<'a>(table: &'a parse::Table, schema: &'a parse::Schema) -> Vec<Reference<'a>> {
+    table
+        .foreign_keys()
+        .iter()
+        .filter_map(|fk| {
+            let ([source], [target]) = (fk.source_columns.as_slice(), fk.target_columns.as_slice())
+            else {
+                return None;
+            };
+            let parent = schema.table(&fk.target_table)?;
+            if parent.primary_key().len() != 1 || !parent.is_primary_key(target) {
+                return None;
+            }
+            Some(Reference {
+                column: table.column(source)?,
+                parent,
+                target: parent.column(target)?,
+            })
+        })
+        .collect()
+}
+
+/// The references of `table` that get nested routes: those onto a parent
+/// no other reference of `table` points at.
+pub fn nested<'a>(table: &'a parse::Table, schema: &'a parse::Schema) -> Vec<Reference<'a>> {
+    let mut references = references(table, schema);
+    let parents: Vec<String> = references.iter().map(|r| r.parent.name.clone()).collect();
+    references.retain(|r| parents.iter().filter(|p| **p == r.parent.name).count() == 1);
+    references
+}
+
+/// Whether `reference` gets a nested POST. A server-maintained column is not
+/// part of the create model, so there is nothing to fill in from the path.
+pub fn creates(table: &parse::Table, reference: &Reference, config: &Config) -> bool {
+    !crate::repository::is_managed(table, reference.column, config)
+}
+
+/// The two references of a join table, whose primary key is exactly their columns.
+pub fn join<'r, 'a>(
+    table: &parse::Table,
+    references: &'r [Reference<'a>],
+) -> Option<(&'r Reference<'a>, &'r Reference<'a>)> {
+    match references {
+        [a, b]
+            if table.primary_key().len() == 2
+                && table.is_primary_key(&a.column.name)
+                && table.is_primary_key(&b.column.name)
+                && a.column.name != b.column.name =>
+        {
+            Some((a, b))
+        }
+        _ => None,
+    }
+}
+
+fn ident(name: String) -> syn::Ident {
+    syn::Ident::new(&name, proc_macro2::Span::call_site())
+}
+
+fn lower(table: &parse::Table) -> String {
+    table.name.trim_matches('"').to_lowercase()
+}
+
+/// Nested handlers for `table`, and their names in registration order.
+pub fn routes(
+    table: &parse::Table,
+    schema: &parse::Schema,
+    config: &Config,
+) -> (proc_macro2::TokenStream, Vec<syn::Ident>) {
+    let mut output = proc_macro2::TokenStream::new();
+    let mut services = Vec::new();
+    let names = Names::new(table, config);
+    let repository = &names.repository;
+    let respond = list::respond(repository);
+    let references = nested(table, schema);
+
+    for reference in &references {
+        let parent = reference.parent;
+        let route = format!(
+            "/{}/{{{}}}/{}",
+            parent.name,
+            field_ident(&reference.target.name),
+            table.name
+        );
+        let ty = types::column_type(&table.name, reference.column, schema, config);
+        let before = format!("{} = ", reference.column.name);
+        let cast = list::cast_type(&reference.column.dtype, schema);
+
+        let get = ident(format!("get_{}_{}_handler", lower(parent), lower(table)));
+        output.extend(quote! {
+            #[get(#route)]
+            async fn #get(
+                req: actix_web::HttpRequest,
+                path: web::Path<(#ty,)>,
+                pool: web::Data<PgPool>,
+            ) -> Result<HttpResponse, ApiError> {
+                let mut list = list_query::ListQuery::parse(req.query_string(), &#repository::TABLE)?;
+                list.scopes.push(list_query::Scope {
+                    before: #before,
+                    cast: #cast,
+                    after: "",
+                    value: text_value(&path.0),
+                });
+                #respond
+            }
+        });
+        services.push(get);
+
+        if !creates(table, reference, config) {
+            continue;
+        }
+        let new_model = &names.new_model;
+        let field = json_name(&reference.column.name);
+        let key_names = table.primary_key().into_iter().map(|c| field_ident(&c));
+        let location = if table.primary_key().is_empty() {
+            quote! {}
+        } else {
+            let collection = &table.name;
+            quote! {
+                // Strip `/{parent}/{key}/{table}` to get the scope prefix, if any.
+                let base = req.path().rsplitn(4, '/').last().unwrap_or("");
+                let segments: Vec<String> = vec![#(location_segment(&row.#key_names)),*];
+                let location = format!("{}/{}/{}", base, #collection, segments.join("/"));
+                response.insert_header((actix_web::http::header::LOCATION, location));
+            }
+        };
+        let post = ident(format!("post_{}_{}_handler", lower(parent), lower(table)));
+        output.extend(quote! {
+            #[post(#route)]
+            async fn #post(
+                req: actix_web::HttpRequest,
+                path: web::Path<(#ty,)>,
+                body: web::Json<serde_json::Map<String, serde_json::Value>>,
+                pool: web::Data<PgPool>,
+            ) -> Result<HttpResponse, ApiError> {
+                let mut body = body.into_inner();
+                let parent = serde_json::to_value(&path.0).map_err(|e| ApiError::bad_request(e.to_string()))?;
+                body.insert(String::from(#field), parent);
+                let new: #new_model = serde_json::from_value(serde_json::Value::Object(body))
+                    .map_err(|e| ApiError::bad_request(e.to_string()))?;
+                let mut tx = audit::begin(&pool, &req).await?;
+                let row = #repository::insert(&mut *tx, &new).await?;
+                tx.commit().await?;
+                let mut response = HttpResponse::Created();
+                #location
+                Ok(response.json(row))
+            }
+        });
+        services.push(post);
+    }
+
+    if let Some((a, b)) = join(table, &references) {
+        output.extend(link(table, schema, config, repository));
+        for (from, to) in [(a, b), (b, a)] {
+            let (handlers, names) = join_routes(table, schema, config, from, to);
+            output.extend(handlers);
+            services.extend(names);
+        }
+    }
+
+    (output, services)
+}
+
+// `Repository::link`, inserting the key columns of a join table and its audit
+// columns. A soft deleted link is restored.
+fn link(
+    table: &parse::Table,
+    schema: &parse::Schema,
+    config: &Config,
+    repository: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    let key = &Names::new(table, config).key;
+    let key_columns = table.primary_key();
+    let key_names = key_columns.iter().map(|c| field_ident(c));
+    let mut columns = key_columns.clone();
+    let mut values = vec![String::from("$1"), String::from("$2")];
+    for (column, value) in crate::audit::on_insert(table, schema, config) {
+        columns.push(column);
+        values.push(value);
+    }
+    let conflict = match crate::soft_delete::column(table, config) {
+        Some(column) => format!(
+            "ON CONFLICT ({}) DO UPDATE SET {1} = NULL WHERE {2}.{1} IS NOT NULL",
+            key_columns.join(", "),
+            column.name,
+            table.name
+        ),
+        None => String::from("ON CONFLICT DO NOTHING"),
+    };
+    let insert = format!(
+        "INSERT INTO {} ({}) VALUES ({}) {}",
+        table.name,
+        columns.join(", "),
+        values.join(", "),
+        conflict
+    );
+    quote! {
+        impl #repository {
+            /// Links the two rows, returning whether the link is new.
+            pub async fn link<'e, E>(executor: E, key: &#key) -> Result<bool, sqlx::Error>
+            where
+                E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+            {
+                let result = sqlx::query(#insert)
+                    #(.bind(&key.#key_names))*
+                    .execute(executor)
+                    .await?;
+                Ok(result.rows_affected() > 0)
+            }
+        }
+    }
+}
+
+// `GET /{from}/{id}/{to}` and link/unlink of one `to` row, through `table`.
+fn join_routes(
+    table: &parse::Table,
+    schema: &parse::Schema,
+    config: &Config,
+    from: &Reference,
+    to: &Reference,
+) -> (proc_macro2::TokenStream, Vec<syn::Ident>) {
+    let join = Names::new(table, config);
+    let join_repository = &join.repository;
+    let key = &join.key;
+    let target = Names::new(to.parent, config).repository;
+    let respond = list::respond(&target);
+    let from_field = field_ident(&from.column.name);
+    let to_field = field_ident(&to.column.name);
+    let route = format!(
+        "/{}/{{{}}}/{}",
+        from.parent.name, from_field, to.parent.name
+    );
+    let member = format!("{}/{{{}}}", route, to_field);
+    let ty = types::column_type(&table.name, from.column, schema, config);
+    // assets.asset_id IN (SELECT asset_collection.asset_id FROM asset_collection
+    //     WHERE asset_collection.collection_id = $1)
+    let before = format!(
+        "{}.{} IN (SELECT {}.{} FROM {} WHERE {}.{} = ",
+        to.parent.name,
+        to.target.name,
+        table.name,
+        to.column.name,
+        table.name,
+        table.name,
+        from.column.name
+    );
+    let cast = list::cast_type(&from.column.dtype, schema);
+    let after = match crate::soft_delete::column(table, config) {
+        Some(column) => format!(" AND {}.{} IS NULL)", table.name, column.name),
+        None => String::from(")"),
+    };
+    let not_found = format!("{} not found", table.name);
+    let suffix = format!("{}_{}_handler", lower(from.parent), lower(to.parent));
+    let get = ident(format!("get_{}", suffix));
+    let put = ident(format!("link_{}", suffix));
+    let delete = ident(format!("unlink_{}", suffix));
+    let handlers = quote! {
+        #[get(#route)]
+        async fn #get(
+            req: actix_web::HttpRequest,
+            path: web::Path<(#ty,)>,
+            pool: web::Data<PgPool>,
+        ) -> Result<HttpResponse, ApiError> {
+            let mut list = list_query::ListQuery::parse(req.query_string(), &#target::TABLE)?;
+            list.scopes.push(list_query::Scope {
+                before: #before,
+                cast: #cast,
+                after: #after,
+                value: text_value(&path.0),
+            });
+            #respond
+        }
+
+        #[actix_web::put(#member)]
+        async fn #put(
+            req: actix_web::HttpRequest,
+            path: web::Path<#key>,
+            pool: web::Data<PgPool>,
+        ) -> Result<HttpResponse, ApiError> {
+            let mut tx = audit::begin(&pool, &req).await?;
+            #join_repository::link(&mut *tx, &path).await?;
+            tx.commit().await?;
+            Ok(HttpResponse::NoContent().finish())
+        }
+
+        #[delete(#member)]
+        async fn #delete(
+            req: actix_web::HttpRequest,
+            path: web::Path<#key>,
+            pool: web::Data<PgPool>,
+        ) -> Result<HttpResponse, ApiError> {
+            let mut tx = audit::begin(&pool, &req).await?;
+            if !#join_repository::delete(&mut *tx, &path).await? {
+                return Err(ApiError::not_found(#not_found));
+            }
+            tx.commit().await?;
+            Ok(HttpResponse::NoContent().finish())
+        }
+    };
+    (handlers, vec![get, put, delete])
+}
@@ -0,0 +1,1104 @@
+// OpenAPI 3.1 description of the generated API, built while the macro expands
+// so it always matches the routes it registers:
+//
+//     GET /openapi.json                     the document
+//     GET /docs                             Swagger UI for it
+//
+// Every table gets schemas for its row, create, patch and key models, and
+// every route lists its parameters, body, responses and headers. Failures
+// refer to shared problem details responses, see `errors.rs`.
+//
+// Paths leave out the `prefix` and wherever the application mounts
+// `configure`; the served document's `servers` entry points there instead.
+// Swagger UI is bundled from `assets/swagger-ui`, so `/docs` works offline.
+
+use crate::config::Config;
+use crate::nested::{self, Reference};
+use crate::repository::{self, Names};
+use crate::{list, soft_delete, types};
+use quote::quote;
+use serde_json::{json, Map, Value};
+
+const SWAGGER_UI: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/swagger-ui");
+
+// Unquoted column name, as the generated structs serialize it.
+fn name(column: &parse::Column) -> String {
+    column.name.trim_matches('"').to_string()
+}
+
+fn lower(table: &parse::Table) -> String {
+    table.name.trim_matches('"').to_lowercase()
+}
+
+fn component(kind: &str, name: &str) -> Value {
+    json!({ "$ref": format!("#/components/{}/{}", kind, name) })
+}
+
+fn schema_ref(name: &impl ToString) -> Value {
+    component("schemas", &name.to_string())
+}
+
+// JSON Schema for a Rust type as written by `types::column_type`, serialized
+// the way serde does it.
+fn rust_schema(ty: &str, schema: &parse::Schema) -> Option<Value> {
+    if let Some(element) = ty.strip_prefix("Vec<").and_then(|t| t.strip_suffix('>')) {
+        let items = match element {
+            "u8" => json!({ "type": "integer", "minimum": 0, "maximum": 255 }),
+            _ => rust_schema(element, schema)?,
+        };
+        return Some(json!({ "type": "array", "items": items }));
+    }
+    if let Some(element) = ty
+        .strip_prefix("pg_types::Range<")
+        .and_then(|t| t.strip_suffix('>'))
+    {
+        let value = rust_schema(element, schema)?;
+        let bound = json!({ "oneOf": [
+            { "const": "Unbounded" },
+            { "type": "object", "properties": { "Included": value }, "required": ["Included"] },
+            { "type": "object", "properties": { "Excluded": value }, "required": ["Excluded"] },
+        ] });
+        return Some(json!({
+            "type": "object",
+            "properties": { "start": bound, "end": bound },
+            "required": ["start", "end"],
+        }));
+    }
+    let value = match ty {
+        "bool" => json!({ "type": "boolean" }),
+        "i8" | "i16" | "i32" => json!({ "type": "integer", "format": "int32" }),
+        "i64" => json!({ "type": "integer", "format": "int64" }),
+        "f32" => json!({ "type": "number", "format": "float" }),
+        "f64" => json!({ "type": "number", "format": "double" }),
+        "String" => json!({ "type": "string" }),
+        "rust_decimal::Decimal" => json!({ "type": "string", "format": "decimal" }),
+        "uuid::Uuid" => json!({ "type": "string", "format": "uuid" }),
+        "serde_json::Value" => json!({}),
+        "ipnetwork::IpNetwork" => json!({ "type": "string", "examples": ["10.0.0.0/8"] }),
+        "mac_address::MacAddress" => json!({ "type": "string", "examples": ["08:00:2b:01:02:03"] }),
+        "chrono::NaiveDate" | "time::Date" => json!({ "type": "string", "format": "date" }),
+        "chrono::NaiveTime" | "time::Time" => json!({ "type": "string", "format": "time" }),
+        "chrono::NaiveDateTime" | "time::PrimitiveDateTime" => {
+            json!({ "type": "string", "examples": ["2024-01-01T12:00:00"] })
+        }
+        "chrono::DateTime<chrono::Utc>" | "time::OffsetDateTime" => {
+            json!({ "type": "string", "format": "date-time" })
+        }
+        "sqlx::postgres::types::Oid" => {
+            json!({ "type": "integer", "minimum": 0, "maximum": u32::MAX })
+        }
+        "pg_types::Money" => json!({ "type": "integer", "format": "int64" }),
+        "pg_types::Interval" => json!({
+            "type": "object",
+            "properties": {
+                "months": { "type": "integer", "format": "int32" },
+                "days": { "type": "integer", "format": "int32" },
+                "microseconds": { "type": "integer", "format": "int64" },
+            },
+            "required": ["months", "days", "microseconds"],
+        }),
+        "pg_types::TimeTz" => json!({
+            "type": "object",
+            "properties": {
+                "time": { "type": "string", "format": "time" },
+                "offset_seconds": { "type": "integer", "format": "int32" },
+            },
+            "required": ["time", "offset_seconds"],
+        }),
+        _ => {
+            let enum_type = schema
+                .enums
+                .iter()
+                .find(|e| types::camel_case(e.name.trim_matches('"')) == ty)?;
+            schema_ref(&types::camel_case(enum_type.name.trim_matches('"')))
+        }
+    };
+    Some(value)
+}
+
+// Schema of a column's value. A type override the document knows nothing
+// about, like `crate::Email`, is described by the column's SQL type.
+fn column_schema(
+    table: &parse::Table,
+    column: &parse::Column,
+    schema: &parse::Schema,
+    config: &Config,
+) -> Value {
+    let rust = |ty: syn::Type| quote!(#ty).to_string().replace(' ', "");
+    let ty = types::column_type(&table.name, column, schema, config);
+    rust_schema(&rust(ty), schema)
+        .or_else(|| {
+            let ty = types::resolve(&column.dtype, schema, &Config::default())?;
+            rust_schema(&rust(ty), schema)
+        })
+        .unwrap_or_else(|| json!({ "type": "string" }))
+}
+
+// The schema, or null.
+fn nullable(value: Value) -> Value {
+    match value.get("type").and_then(Value::as_str) {
+        Some(ty) => {
+            let mut value = value.clone();
+            value["type"] = json!([ty, "null"]);
+            value
+        }
+        None if value.as_object().is_some_and(Map::is_empty) => value,
+        None => json!({ "anyOf": [value, { "type": "null" }] }),
+    }
+}
+
+fn object(description: String, properties: Map<String, Value>, required: Vec<String>) -> Value {
+    let mut object = json!({
+        "type": "object",
+        "description": description,
+        "properties": properties,
+    });
+    if !required.is_empty() {
+        object["required"] = json!(required);
+    }
+    object
+}
+
+// Row, create, patch and key models of `table`, named like the structs.
+fn models(
+    table: &parse::Table,
+    schema: &parse::Schema,
+    config: &Config,
+    schemas: &mut Map<String, Value>,
+) {
+    let names = Names::new(table, config);
+    let property = |column: &parse::Column| {
+        let value = column_schema(table, column, schema, config);
+        if table.is_nullable(column) {
+            nullable(value)
+        } else {
+            value
+        }
+    };
+
+    let columns = &table.columns;
+    schemas.insert(
+        names.model.to_string(),
+        object(
+            format!("A row of `{}`.", table.name),
+            columns.iter().map(|c| (name(c), property(c))).collect(),
+            columns.iter().map(name).collect(),
+        ),
+    );
+
+    let insert = repository::insert_columns(table, config);
+    schemas.insert(
+        names.new_model.to_string(),
+        object(
+            format!(
+                "A new row of `{}`. Server-maintained columns are left out, and a \
+                 defaulted column takes its default when absent.",
+                table.name
+            ),
+            insert.iter().map(|c| (name(c), property(c))).collect(),
+            insert
+                .iter()
+                .filter(|c| !repository::is_optional(table, c))
+                .map(|c| name(c))
+                .collect(),
+        ),
+    );
+
+    let patch: Vec<&parse::Column> = columns
+        .iter()
+        .filter(|c| !table.is_primary_key(&c.name) && !repository::is_managed(table, c, config))
+        .collect();
+    schemas.insert(
+        names.patch.to_string(),
+        object(
+            String::from("Columns to change; absent ones are left alone."),
+            patch.iter().map(|c| (name(c), property(c))).collect(),
+            Vec::new(),
+        ),
+    );
+
+    let key = key_columns(table);
+    if !key.is_empty() {
+        schemas.insert(
+            names.key.to_string(),
+            object(
+                format!("Primary key of `{}`.", table.name),
+                key.iter()
+                    .map(|c| (name(c), column_schema(table, c, schema, config)))
+                    .collect(),
+                key.iter().map(|c| name(c)).collect(),
+            ),
+        );
+    }
+}
+
+fn key_columns(table: &parse::Table) -> Vec<&parse::Column> {
+    table
+        .primary_key()
+        .iter()
+        .filter_map(|name| table.column(name))
+        .collect()
+}
+
+fn path_parameter(
+    table: &parse::Table,
+    column: &parse::Column,
+    schema: &parse::Schema,
+    config: &Config,
+) -> Value {
+    json!({
+        "name": name(column),
+        "in": "path",
+        "required": true,
+        "schema": column_schema(table, column, schema, config),
+    })
+}
+
+fn json_content(schema: Value) -> Value {
+    json!({ "application/json": { "schema": schema } })
+}
+
+fn rows(model: &syn::Ident) -> Value {
+    json!({ "type": "array", "items": schema_ref(model) })
+}
+
+// `{ "200": ..., "404": <ref>, ... }` with `errors` as shared responses.
+fn responses(ok: &[(&str, Value)], errors: &[&str]) -> Value {
+    let mut responses = Map::new();
+    for (status, response) in ok {
+        responses.insert(status.to_string(), response.clone());
+    }
+    for error in errors {
+        let name = match *error {
+            "400" => "BadRequest",
+            "404" => "NotFound",
+            "409" => "Conflict",
+            "412" => "PreconditionFailed",
+            "413" => "PayloadTooLarge",
+            _ => "UnprocessableEntity",
+        };
+        responses.insert(error.to_string(), component("responses", name));
+    }
+    responses.insert(String::from("default"), component("responses", "Error"));
+    Value::Object(responses)
+}
+
+fn response(description: &str, content: Option<Value>, headers: &[&str]) -> Value {
+    let mut response = json!({ "description": description });
+    if let Some(content) = content {
+        response["content"] = content;
+    }
+    if !headers.is_empty() {
+        let headers: Map<String, Value> = headers
+            .iter()
+            .map(|h| (h.to_string(), component("headers", h)))
+            .collect();
+        response["headers"] = Value::Object(headers);
+    }
+    response
+}
+
+struct Operation {
+    id: String,
+    tag: String,
+    summary: String,
+    parameters: Vec<Value>,
+    body: Option<Value>,
+    responses: Value,
+}
+
+impl Operation {
+    fn new(table: &parse::Table, id: String, summary: String, responses: Value) -> Self {
+        Operation {
+            id,
+            tag: table.name.trim_matches('"').to_string(),
+            summary,
+            parameters: Vec::new(),
+            body: None,
+            responses,
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let mut operation = json!({
+            "operationId": self.id,
+            "tags": [self.tag],
+            "summary": self.summary,
+            "responses": self.responses,
+        });
+        if !self.parameters.is_empty() {
+            operation["parameters"] = json!(self.parameters);
+        }
+        if let Some(body) = &self.body {
+            operation["requestBody"] =
+                json!({ "required": true, "content": json_content(body.clone()) });
+        }
+        operation
+    }
+}
+
+fn add(paths: &mut Map<String, Value>, path: String, method: &str, operation: Operation) {
+    let item = paths.entry(path).or_insert_with(|| json!({}));
+    item[method] = operation.to_json();
+}
+
+// Filters, paging, `sort`, `fields` and `expand` of a list of `table`'s rows.
+fn list_parameters(table: &parse::Table, schema: &parse::Schema, config: &Config) -> Vec<Value> {
+    let mut parameters: Vec<Value> = table
+        .columns
+        .iter()
+        .map(|column| {
+            let (key, operators) = match list::kind(&column.dtype, schema) {
+                "Text" => (name(column), "ne, gt, gte, lt, lte, in, is, like, ilike"),
+                "Ordered" => (name(column), "ne, gt, gte, lt, lte, in, is"),
+                "Plain" => (name(column), "ne, in, is"),
+                _ => (format!("{}[is]", name(column)), "is"),
+            };
+            json!({
+                "name": key,
+                "in": "query",
+                "description": format!(
+                    "Filter on `{}`. Also `{}[op]=value` with op one of: {}; \
+                     `in` takes a comma-separated list, `is` takes `null` or `not_null`.",
+                    name(column),
+                    name(column),
+                    operators
+                ),
+                "schema": { "type": "string" },
+            })
+        })
+        .collect();
+    let columns: Vec<String> = table.columns.iter().map(name).collect();
+    parameters.push(json!({
+        "name": "sort",
+        "in": "query",
+        "description": format!(
+            "Comma-separated columns to order by, `-` for descending: {}.",
+            columns.join(", ")
+        ),
+        "schema": { "type": "string" },
+    }));
+    parameters.push(json!({
+        "name": "fields",
+        "in": "query",
+        "description": format!("Comma-separated columns to return: {}.", columns.join(", ")),
+        "schema": { "type": "string" },
+    }));
+    let references: Vec<String> = nested::references(table, schema)
+        .iter()
+        .map(|r| name(r.column))
+        .collect();
+    if !references.is_empty() && config.max_expand_depth() > 0 {
+        parameters.push(json!({
+            "name": "expand",
+            "in": "query",
+            "description": format!(
+                "Comma-separated foreign keys to replace with the rows they reference, \
+                 dotted paths up to {} levels deep: {}.",
+                config.max_expand_depth(),
+                references.join(", ")
+            ),
+            "schema": { "type": "string" },
+        }));
+    }
+    parameters.push(component("parameters", "limit"));
+    parameters.push(component("parameters", "offset"));
+    if !table.primary_key().is_empty() {
+        parameters.push(component("parameters", "cursor"));
+    }
+    if soft_delete::column(table, config).is_some() {
+        parameters.push(component("parameters", "include_deleted"));
+    }
+    parameters
+}
+
+fn page(model: &syn::Ident) -> Value {
+    response(
+        "A page of rows, only the requested `fields` and with `expand`ed foreign keys.",
+        Some(json_content(rows(model))),
+        &["X-Total-Count", "Link"],
+    )
+}
+
+// Collection, bulk, lookup and by-key routes of `table`.
+fn table_paths(
+    table: &parse::Table,
+    schema: &parse::Schema,
+    config: &Config,
+    paths: &mut Map<String, Value>,
+) {
+    let names = Names::new(table, config);
+    let t = lower(table);
+    let collection = format!("/{}", table.name);
+
+    let mut list = Operation::new(
+        table,
+        format!("get_{}", t),
+        format!("List {}", table.name),
+        responses(&[("200", page(&names.model))], &["400"]),
+    );
+    list.parameters = list_parameters(table, schema, config);
+    add(paths, collection.clone(), "get", list);
+
+    let targets: Vec<String> = repository::conflict_targets(table, config)
+        .into_iter()
+        .flat_map(|(name, columns)| name.into_iter().chain([columns.join(",")]))
+        .collect();
+    let created = if table.primary_key().is_empty() {
+        response("Created", Some(json_content(schema_ref(&names.model))), &[])
+    } else {
+        response(
+            "Created",
+            Some(json_content(schema_ref(&names.model))),
+            &["Location"],
+        )
+    };
+    let mut ok = vec![("201", created)];
+    if !targets.is_empty() {
+        let updated = "The row already existed and was updated (`on_conflict`).";
+        ok.push((
+            "200",
+            response(updated, Some(json_content(schema_ref(&names.model))), &[]),
+        ));
+    }
+    let mut create = Operation::new(
+        table,
+        format!("post_{}", t),
+        format!("Create a row of {}", table.name),
+        responses(&ok, &["400", "409", "422"]),
+    );
+    if !targets.is_empty() {
+        create.parameters.push(json!({
+            "name": "on_conflict",
+            "in": "query",
+            "description": "Update the row sharing this unique key, by name or columns, \
+                            instead of failing with 409.",
+            "schema": { "type": "string", "enum": targets },
+        }));
+    }
+    create.body = Some(schema_ref(&names.new_model));
+    add(paths, collection.clone(), "post", create);
+
+    let mut delete = Operation::new(
+        table,
+        format!("delete_{}", t),
+        format!(
+            "Delete the rows of {} matching a filter, at most {}",
+            table.name,
+            config.max_batch_size()
+        ),
+        responses(
+            &[(
+                "200",
+                response(
+                    "Rows deleted",
+                    Some(json_content(json!({
+                        "type": "object",
+                        "properties": { "deleted": { "type": "integer", "format": "int64" } },
+                        "required": ["deleted"],
+                    }))),
+                    &[],
+                ),
+            )],
+            &["400", "422"],
+        ),
+    );
+    delete.parameters = list_parameters(table, schema, config)
+        .into_iter()
+        .filter(|p| {
+            p.get("$ref").is_none()
+                && !matches!(p["name"].as_str(), Some("sort" | "fields" | "expand"))
+        })
+        .collect();
+    add(paths, collection, "delete", delete);
+
+    let bulk = format!("/{}/bulk", table.name);
+    if !repository::insert_columns(table, config).is_empty() {
+        let mut create = Operation::new(
+            table,
+            format!("post_{}_bulk", t),
+            format!("Create rows of {} in one transaction", table.name),
+            responses(
+                &[(
+                    "201",
+                    response("Created", Some(json_content(rows(&names.model))), &[]),
+                )],
+                &["400", "413", "422"],
+            ),
+        );
+        create.body = Some(json!({ "type": "array", "items": schema_ref(&names.new_model) }));
+        add(paths, bulk.clone(), "post", create);
+    }
+    let key = key_columns(table);
+    if !key.is_empty() {
+        let mut update = Operation::new(
+            table,
+            format!("patch_{}_bulk", t),
+            format!("Update rows of {} by key in one transaction", table.name),
+            responses(
+                &[(
+                    "200",
+                    response("Updated", Some(json_content(rows(&names.model))), &[]),
+                )],
+                &["400", "413", "422"],
+            ),
+        );
+        update.body = Some(json!({
+            "type": "array",
+            "items": { "allOf": [schema_ref(&names.key), schema_ref(&names.patch)] },
+        }));
+        add(paths, bulk, "patch", update);
+    }
+
+    for columns in repository::lookups(table) {
+        let lookup = repository::lookup_name(&columns);
+        let segments: Vec<String> = columns.iter().map(|c| format!("{{{}}}", name(c))).collect();
+        let mut get = Operation::new(
+            table,
+            format!("get_{}_by_{}", t, lookup),
+            format!(
+                "Get a row of {} by {}",
+                table.name,
+                lookup.replace("_and_", " and ")
+            ),
+            responses(
+                &[(
+                    "200",
+                    response("The row", Some(json_content(schema_ref(&names.model))), &[]),
+                )],
+                &["400", "404"],
+            ),
+        );
+        get.parameters = columns
+            .iter()
+            .map(|c| path_parameter(table, c, schema, config))
+            .collect();
+        add(
+            paths,
+            format!("/{}/by_{}/{}", table.name, lookup, segments.join("/")),
+            "get",
+            get,
+        );
+    }
+
+    if key.is_empty() {
+        return;
+    }
+    let key_names: Vec<String> = key.iter().map(|c| name(c)).collect();
+    let key_path: Vec<String> = key_names.iter().map(|k| format!("{{{}}}", k)).collect();
+    let member = format!("/{}/{}", table.name, key_path.join("/"));
+    let key_parameters: Vec<Value> = key
+        .iter()
+        .map(|c| path_parameter(table, c, schema, config))
+        .collect();
+    let by_key = key_names.join("_and_");
+    let row = |description: &str| {
+        response(
+            description,
+            Some(json_content(schema_ref(&names.model))),
+            &["ETag"],
+        )
+    };
+    let deleted = soft_delete::column(table, config).is_some();
+
+    if deleted {
+        let mut restore = Operation::new(
+            table,
+            format!("restore_{}", t),
+            format!("Restore a deleted row of {}", table.name),
+            responses(&[("200", row("The restored row"))], &["400", "404"]),
+        );
+        restore.parameters = key_parameters.clone();
+        add(paths, format!("{}/restore", member), "post", restore);
+    }
+
+    let mut get = Operation::new(
+        table,
+        format!("get_{}_by_{}", t, by_key),
+        format!("Get a row of {}", table.name),
+        responses(
+            &[
+                ("200", row("The row, with `expand`ed foreign keys")),
+                (
+                    "304",
+                    response(
+                        "Not modified since the `If-None-Match` ETag",
+                        None,
+                        &["ETag"],
+                    ),
+                ),
+            ],
+            &["400", "404"],
+        ),
+    );
+    get.parameters = key_parameters.clone();
+    get.parameters.extend(
+        list_parameters(table, schema, config)
+            .into_iter()
+            .filter(|p| p["name"] == "expand" || p == &component("parameters", "include_deleted")),
+    );
+    get.parameters
+        .push(component("parameters", "If-None-Match"));
+    add(paths, member.clone(), "get", get);
+
+    let mut delete = Operation::new(
+        table,
+        format!("delete_{}_by_{}", t, by_key),
+        if deleted {
+            format!("Mark a row of {} deleted", table.name)
+        } else {
+            format!("Delete a row of {}", table.name)
+        },
+        responses(
+            &[("204", response("Deleted", None, &[]))],
+            &["400", "404", "412", "422"],
+        ),
+    );
+    delete.parameters = key_parameters.clone();
+    delete.parameters.push(component("parameters", "If-Match"));
+    add(paths, member.clone(), "delete", delete);
+
+    let mut update = Operation::new(
+        table,
+        format!("update_{}", t),
+        format!("Update a row of {}", table.name),
+        responses(
+            &[("200", row("The updated row"))],
+            &["400", "404", "409", "412", "422"],
+        ),
+    );
+    update.parameters = key_parameters.clone();
+    update.parameters.push(component("parameters", "If-Match"));
+    update.body = Some(schema_ref(&names.patch));
+    add(paths, member.clone(), "patch", update);
+
+    let mut upsert = Operation::new(
+        table,
+        format!("upsert_{}", t),
+        format!(
+            "Create or replace a row of {}; key columns come from the path",
+            table.name
+        ),
+        responses(
+            &[
+                (
+                    "201",
+                    response(
+                        "Created",
+                        Some(json_content(schema_ref(&names.model))),
+                        &["Location"],
+                    ),
+                ),
+                (
+                    "200",
+                    response(
+                        "Replaced",
+                        Some(json_content(schema_ref(&names.model))),
+                        &[],
+                    ),
+                ),
+            ],
+            &["400", "409", "422"],
+        ),
+    );
+    upsert.parameters = key_parameters;
+    upsert.body = Some(schema_ref(&names.new_model));
+    add(paths, member, "put", upsert);
+}
+
+// Routes under a parent row, and the link routes of a join table.
+fn nested_paths(
+    table: &parse::Table,
+    schema: &parse::Schema,
+    config: &Config,
+    paths: &mut Map<String, Value>,
+) {
+    let names = Names::new(table, config);
+    let references = nested::nested(table, schema);
+    let parent_parameter = |reference: &Reference| {
+        let mut parameter = path_parameter(table, reference.column, schema, config);
+        parameter["name"] = json!(name(reference.target));
+        parameter
+    };
+
+    for reference in &references {
+        let parent = reference.parent;
+        let route = format!(
+            "/{}/{{{}}}/{}",
+            parent.name,
+            name(reference.target),
+            table.name
+        );
+        let mut get = Operation::new(
+            table,
+            format!("get_{}_{}", lower(parent), lower(table)),
+            format!("List the {} of a row of {}", table.name, parent.name),
+            responses(&[("200", page(&names.model))], &["400"]),
+        );
+        get.parameters = vec![parent_parameter(reference)];
+        get.parameters
+            .extend(list_parameters(table, schema, config));
+        add(paths, route.clone(), "get", get);
+
+        if !nested::creates(table, reference, config) {
+            continue;
+        }
+        let headers: &[&str] = if table.primary_key().is_empty() {
+            &[]
+        } else {
+            &["Location"]
+        };
+        let mut post = Operation::new(
+            table,
+            format!("post_{}_{}", lower(parent), lower(table)),
+            format!(
+                "Create a row of {} under a row of {}; `{}` comes from the path",
+                table.name,
+                parent.name,
+                name(reference.column)
+            ),
+            responses(
+                &[(
+                    "201",
+                    response(
+                        "Created",
+                        Some(json_content(schema_ref(&names.model))),
+                        headers,
+                    ),
+                )],
+                &["400", "409", "422"],
+            ),
+        );
+        post.parameters = vec![parent_parameter(reference)];
+        post.body = Some(schema_ref(&names.new_model));
+        add(paths, route, "post", post);
+    }
+
+    let Some((a, b)) = nested::join(table, &references) else {
+        return;
+    };
+    for (from, to) in [(a, b), (b, a)] {
+        let target = Names::new(to.parent, config);
+        let route = format!(
+            "/{}/{{{}}}/{}",
+            from.parent.name,
+            name(from.column),
+            to.parent.name
+        );
+        let member = format!("{}/{{{}}}", route, name(to.column));
+        let suffix = format!("{}_{}", lower(from.parent), lower(to.parent));
+        let from_parameter = path_parameter(table, from.column, schema, config);
+        let to_parameter = path_parameter(table, to.column, schema, config);
+
+        let mut get = Operation::new(
+            table,
+            format!("get_{}", suffix),
+            format!(
+                "List the {} linked to a row of {}",
+                to.parent.name, from.parent.name
+            ),
+            responses(&[("200", page(&target.model))], &["400"]),
+        );
+        get.parameters = vec![from_parameter.clone()];
+        get.parameters
+            .extend(list_parameters(to.parent, schema, config));
+        add(paths, route, "get", get);
+
+        let mut link = Operation::new(
+            table,
+            format!("link_{}", suffix),
+            format!(
+                "Link a row of {} to a row of {}",
+                to.parent.name, from.parent.name
+            ),
+            responses(&[("204", response("Linked", None, &[]))], &["400", "422"]),
+        );
+        link.parameters = vec![from_parameter.clone(), to_parameter.clone()];
+        add(paths, member.clone(), "put", link);
+
+        let mut unlink = Operation::new(
+            table,
+            format!("unlink_{}", suffix),
+            format!(
+                "Unlink a row of {} from a row of {}",
+                to.parent.name, from.parent.name
+            ),
+            responses(&[("204", response("Unlinked", None, &[]))], &["400", "404"]),
+        );
+        unlink.parameters = vec![from_parameter, to_parameter];
+        add(paths, member, "delete", unlink);
+    }
+}
+
+fn batch_path(schema: &parse::Schema) -> Value {
+    let tables: Vec<&str> = schema.tables.iter().map(|t| t.name.as_str()).collect();
+    let operation = json!({
+        "type": "object",
+        "required": ["op", "table"],
+        "additionalProperties": false,
+        "properties": {
+            "op": { "type": "string", "enum": ["create", "update", "delete"] },
+            "table": { "type": "string", "enum": tables },
+            "ref": {
+                "type": "string",
+                "description": "Name later operations use to refer to the returned row.",
+            },
+            "key": {
+                "type": "object",
+                "description": "Primary key of the row to update or delete. \
+                                A value may be `{ \"$ref\": \"name.column\" }`.",
+            },
+            "body": {
+                "type": "object",
+                "description": "Columns to create or change. \
+                                A value may be `{ \"$ref\": \"name.column\" }`.",
+            },
+        },
+    });
+    let outcome = json!({
+        "type": "object",
+        "required": ["status"],
+        "properties": {
+            "status": { "type": "integer" },
+            "body": { "type": "object" },
+        },
+    });
+    json!({
+        "post": {
+            "operationId": "batch",
+            "tags": ["batch"],
+            "summary": "Run operations on any table in one transaction",
+            "requestBody": {
+                "required": true,
+                "content": json_content(json!({ "type": "array", "items": operation })),
+            },
+            "responses": responses(
+                &[(
+                    "200",
+                    response(
+                        "One outcome per operation, in order",
+                        Some(json_content(json!({ "type": "array", "items": outcome }))),
+                        &[],
+                    ),
+                )],
+                &["400", "404", "409", "413", "422"],
+            ),
+        },
+    })
+}
+
+fn problem(description: &str) -> Value {
+    json!({
+        "description": description,
+        "content": { "application/problem+json": { "schema": schema_ref(&"Problem") } },
+    })
+}
+
+// Schemas, parameters, headers and responses shared by the paths.
+fn components(schema: &parse::Schema, config: &Config) -> Value {
+    let mut schemas = Map::new();
+    schemas.insert(
+        String::from("Problem"),
+        json!({
+            "type": "object",
+            "description": "RFC 7807 problem details.",
+            "required": ["type", "title", "status"],
+            "properties": {
+                "type": { "type": "string" },
+                "title": { "type": "string" },
+                "status": { "type": "integer" },
+                "detail": { "type": "string" },
+                "errors": {
+                    "type": "array",
+                    "description": "The rows or operations that failed.",
+                    "items": schema_ref(&"RowError"),
+                },
+            },
+        }),
+    );
+    schemas.insert(
+        String::from("RowError"),
+        json!({
+            "type": "object",
+            "required": ["index", "status"],
+            "properties": {
+                "index": { "type": "integer", "description": "Position in the request body." },
+                "status": { "type": "integer" },
+                "detail": { "type": "string" },
+            },
+        }),
+    );
+    for enum_type in &schema.enums {
+        schemas.insert(
+            types::camel_case(enum_type.name.trim_matches('"')),
+            json!({ "type": "string", "enum": enum_type.values }),
+        );
+    }
+    for table in &schema.tables {
+        models(table, schema, config, &mut schemas);
+    }
+
+    json!({
+        "schemas": schemas,
+        "parameters": {
+            "limit": {
+                "name": "limit",
+                "in": "query",
+                "description": format!("Rows per page, {} by default.", config.page_size()),
+                "schema": { "type": "integer", "minimum": 1, "maximum": config.max_page_size() },
+            },
+            "offset": {
+                "name": "offset",
+                "in": "query",
+                "description": "Rows to skip.",
+                "schema": { "type": "integer", "minimum": 0 },
+            },
+            "cursor": {
+                "name": "cursor",
+                "in": "query",
+                "description": "Opaque token from the `next` link, continuing after the last \
+                                row of the previous page.",
+                "schema": { "type": "string" },
+            },
+            "include_deleted": {
+                "name": "include_deleted",
+                "in": "query",
+                "description": "Also return soft deleted rows.",
+                "schema": { "type": "boolean", "default": false },
+            },
+            "If-Match": {
+                "name": "If-Match",
+                "in": "header",
+                "description": "Only change the row while its ETag matches, 412 otherwise.",
+                "schema": { "type": "string" },
+            },
+            "If-None-Match": {
+                "name": "If-None-Match",
+                "in": "header",
+                "description": "Answer 304 when the row's ETag matches.",
+                "schema": { "type": "string" },
+            },
+        },
+        "headers": {
+            "X-Total-Count": {
+                "description": "Rows matching the filters, across all pages.",
+                "schema": { "type": "integer" },
+            },
+            "Link": {
+                "description": "`rel=\"next\"` link to the next page, when there is one.",
+                "schema": { "type": "string" },
+            },
+            "ETag": {
+                "description": "Version of the row, for `If-Match` and `If-None-Match`.",
+                "schema": { "type": "string" },
+            },
+            "Location": {
+                "description": "Path of the created row.",
+                "schema": { "type": "string" },
+            },
+        },
+        "responses": {
+            "BadRequest": problem("Malformed body, path or query string"),
+            "NotFound": problem("No such row"),
+            "Conflict": problem("A unique key is already taken"),
+            "PreconditionFailed": problem("The `If-Match` ETag does not match"),
+            "PayloadTooLarge": problem(&format!(
+                "More than {} rows in one request",
+                config.max_batch_size()
+            )),
+            "UnprocessableEntity": problem(
+                "A foreign key, NOT NULL or CHECK constraint failed; `errors` lists failed rows"
+            ),
+            "Error": problem("Unexpected error"),
+        },
+    })
+}
+
+/// The OpenAPI document for `schema`, without `servers`.
+pub fn document(schema: &parse::Schema, config: &Config) -> Value {
+    let mut paths = Map::new();
+    for table in &schema.tables {
+        table_paths(table, schema, config, &mut paths);
+        nested_paths(table, schema, config, &mut paths);
+    }
+    paths.insert(String::from("/batch"), batch_path(schema));
+    let package =
+        |key: &str, default: &str| std::env::var(key).unwrap_or_else(|_| default.to_string());
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": package("CARGO_PKG_NAME", "api"),
+            "version": package("CARGO_PKG_VERSION", "0.0.0"),
+        },
+        "tags": schema
+            .tables
+            .iter()
+            .map(|t| json!({ "name": t.name.trim_matches('"') }))
+            .chain([json!({ "name": "batch" })])
+            .collect::<Vec<_>>(),
+        "paths": paths,
+        "components": components(schema, config),
+    })
+}
+
+/// `OPENAPI`, the document as JSON, and the `/openapi.json` and `/docs` handlers.
+pub fn support(schema: &parse::Schema, config: &Config) -> proc_macro2::TokenStream {
+    let document = document(schema, config).to_string();
+    let css = format!("{}/swagger-ui.css", SWAGGER_UI);
+    let js = format!("{}/swagger-ui-bundle.js", SWAGGER_UI);
+    let html = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>API documentation</title>
+<link rel="stylesheet" href="docs/swagger-ui.css">
+</head>
+<body>
+<div id="swagger-ui"></div>
+<script src="docs/swagger-ui-bundle.js"></script>
+<script>
+window.ui = SwaggerUIBundle({ url: "openapi.json", dom_id: "#swagger-ui" });
+</script>
+</body>
+</html>
+"##;
+    quote! {
+        /// OpenAPI 3.1 description of the generated routes, served at `/openapi.json`.
+        pub const OPENAPI: &str = #document;
+
+        #[get("/openapi.json")]
+        async fn openapi_handler(req: actix_web::HttpRequest) -> HttpResponse {
+            static DOCUMENT: std::sync::OnceLock<serde_json::Value> = std::sync::OnceLock::new();
+            let mut document = DOCUMENT
+                .get_or_init(|| serde_json::from_str(OPENAPI).expect("invalid OpenAPI document"))
+                .clone();
+            // Paths are relative to wherever `configure` was mounted.
+            let base = req.path().strip_suffix("/openapi.json").unwrap_or_default();
+            if !base.is_empty() {
+                document["servers"] = serde_json::json!([{ "url": base }]);
+            }
+            HttpResponse::Ok().json(document)
+        }
+
+        // The page refers to its assets and the document relative to itself.
+        #[get("/docs")]
+        async fn docs_handler() -> HttpResponse {
+            HttpResponse::Ok()
+                .content_type("text/html; charset=utf-8")
+                .body(#html)
+        }
+
+        #[get("/docs/{file}")]
+        async fn docs_asset_handler(file: web::Path<String>) -> Result<HttpResponse, ApiError> {
+            let (content_type, body): (&str, &'static [u8]) = match file.as_str() {
+                "swagger-ui.css" => ("text/css; charset=utf-8", include_bytes!(#css)),
+                "swagger-ui-bundle.js" => ("text/javascript; charset=utf-8", include_bytes!(#js)),
+                _ => return Err(ApiError::not_found(format!("no such file: {}", file))),
+            };
+            Ok(HttpResponse::Ok()
+                .content_type(content_type)
+                .insert_header((actix_web::http::header::CACHE_CONTROL, "public, max-age=86400"))
+                .body(body))
+        }
+    }
+}
@@ -0,0 +1,848 @@
+// Per-table data access emitted by `generate_structs_from_ddl`.
+//
+// `AssetRepository` owns the SQL for the `assets` table and the generated
+// handlers call into it. Every method takes any `sqlx::Executor`, so the same
+// code runs on a pool, a single connection or an open transaction:
+//
+//     let mut tx = pool.begin().await?;
+//     let asset = AssetRepository::insert(&mut *tx, &new_asset).await?;
+//     tx.commit().await?;
+//
+// On soft deleted tables `delete` marks the row instead, and the other
+// queries skip marked rows; see `soft_delete.rs`.
+
+use crate::config::Config;
+use crate::{field_ident, json_name, types};
+use quote::quote;
+
+/// Identifiers of the structs generated for one table.
+pub struct Names {
+    pub model: syn::Ident,
+    pub new_model: syn::Ident,
+    pub patch: syn::Ident,
+    pub key: syn::Ident,
+    pub repository: syn::Ident,
+}
+
+impl Names {
+    pub fn new(table: &parse::Table, config: &Config) -> Self {
+        let model = types::model_name(&table.name, config);
+        let ident = |name: String| syn::Ident::new(&name, proc_macro2::Span::call_site());
+        Names {
+            model: ident(model.clone()),
+            new_model: ident(format!("New{}", model)),
+            patch: ident(format!("{}Patch", model)),
+            key: ident(format!("{}Key", model)),
+            repository: ident(format!("{}Repository", model)),
+        }
+    }
+}
+
+/// `a, b, c` as written in the DDL, for SELECT and RETURNING lists.
+pub fn column_list(table: &parse::Table) -> String {
+    table
+        .columns
+        .iter()
+        .map(|col| col.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// `a = $1 AND b = $2`, numbering from `first`.
+fn where_clause(columns: &[&parse::Column], first: usize) -> String {
+    columns
+        .iter()
+        .enumerate()
+        .map(|(i, col)| format!("{} = ${}", col.name, first + i))
+        .collect::<Vec<_>>()
+        .join(" AND ")
+}
+
+/// Unique keys other than the primary key, each looked up by `find_by_<columns>`.
+pub fn lookups(table: &parse::Table) -> Vec<Vec<&parse::Column>> {
+    let primary_key = table.primary_key();
+    let mut keys: Vec<Vec<&parse::Column>> = Vec::new();
+    for unique in table.unique_keys() {
+        let columns: Option<Vec<&parse::Column>> = unique
+            .columns
+            .iter()
+            .map(|name| table.column(name))
+            .collect();
+        let Some(columns) = columns else {
+            continue;
+        };
+        let names: Vec<&str> = columns.iter().map(|c| c.name.as_str()).collect();
+        if primary_key == names
+            || keys
+                .iter()
+                .any(|key| lookup_name(key) == lookup_name(&columns))
+        {
+            continue;
+        }
+        keys.push(columns);
+    }
+    keys
+}
+
+/// `asset_name`, or `asset_id_and_collection_id` for a composite key.
+pub fn lookup_name(columns: &[&parse::Column]) -> String {
+    columns
+        .iter()
+        .map(|c| json_name(&c.name).to_lowercase())
+        .collect::<Vec<_>>()
+        .join("_and_")
+}
+
+/// Whether the server maintains `column`, as an audit, soft delete or ETag
+/// version column, so it is left out of client input.
+pub fn is_managed(table: &parse::Table, column: &parse::Column, config: &Config) -> bool {
+    crate::audit::is_audit(table, column, config)
+        || crate::soft_delete::is_column(table, column, config)
+        || crate::etag::version_column(table, config).is_some_and(|v| v.name == column.name)
+}
+
+/// Whether a create model field may be absent: the column is nullable or has
+/// a default, which then applies.
+pub fn is_optional(table: &parse::Table, column: &parse::Column) -> bool {
+    table.is_nullable(column) || column.has_default()
+}
+
+/// Columns of the create model: all but those maintained by the server.
+/// Defaulted ones are optional, see [`is_optional`].
+pub fn insert_columns<'a>(table: &'a parse::Table, config: &Config) -> Vec<&'a parse::Column> {
+    table
+        .columns
+        .iter()
+        .filter(|c| !is_managed(table, c, config))
+        .collect()
+}
+
+/// Unique keys a POST may name in `?on_conflict=`, by constraint or index
+/// name (when there is one) and columns. Only keys whose columns are all in
+/// the create model qualify; a conflict on a server-maintained value cannot
+/// happen.
+pub fn conflict_targets(
+    table: &parse::Table,
+    config: &Config,
+) -> Vec<(Option<String>, Vec<String>)> {
+    let insert_columns = insert_columns(table, config);
+    let primary_key = table.constraints.iter().find_map(|c| match c {
+        parse::Constraint::PrimaryKey(pk) => Some((pk.name.clone(), pk.columns.clone())),
+        _ => None,
+    });
+    let uniques = table.unique_keys().into_iter().map(|u| (u.name, u.columns));
+    let mut targets: Vec<(Option<String>, Vec<String>)> = Vec::new();
+    for (name, columns) in primary_key.into_iter().chain(uniques) {
+        let supplied = columns
+            .iter()
+            .all(|name| insert_columns.iter().any(|c| c.name == *name));
+        if supplied && !targets.iter().any(|(_, seen)| *seen == columns) {
+            targets.push((name, columns));
+        }
+    }
+    targets
+}
+
+// Body of an upsert: `INSERT INTO t (a, b) VALUES ($1, $2) ON CONFLICT (a) DO
+// UPDATE SET b = EXCLUDED.b RETURNING ..., (xmax = 0)`, the last column telling
+// an insert from an update. `target` names the conflict columns, `a,b`, and
+// each value comes from `key` or `new`. A `defaulted` column of `new` is only
+// written when present, so an insert gets the default and an update leaves the
+// column alone. Audit columns are written on insert, and only the `updated_*`
+// ones and the version on update. An update brings back a soft deleted row.
+fn upsert_body(
+    table: &parse::Table,
+    model: &syn::Ident,
+    values: &[(&parse::Column, proc_macro2::TokenStream)],
+    defaulted: &[&parse::Column],
+    target: proc_macro2::TokenStream,
+    audit: &Audit,
+    deleted: Option<&parse::Column>,
+) -> proc_macro2::TokenStream {
+    let (present_columns, present_values): (Vec<_>, Vec<_>) = values
+        .iter()
+        .map(|(col, value)| {
+            let name = &col.name;
+            if defaulted.iter().any(|c| c.name == col.name) {
+                (
+                    quote! { if #value.is_some() { columns.push(#name); } },
+                    quote! { if let Some(value) = &#value { values.push_bind(value); } },
+                )
+            } else {
+                (
+                    quote! { columns.push(#name); },
+                    quote! { values.push_bind(&#value); },
+                )
+            }
+        })
+        .unzip();
+    let touch: Vec<String> = audit
+        .update
+        .iter()
+        .map(|(name, value)| format!("{} = {}", name, value))
+        .chain(deleted.map(|column| format!("{} = NULL", column.name)))
+        .collect();
+    let audit_columns = audit.insert.iter().map(|(name, _)| name);
+    let audit_values = audit.insert.iter().map(|(_, value)| value);
+    let insert_into = format!("INSERT INTO {} (", table.name);
+    let returning = format!(" RETURNING {}, (xmax = 0)", column_list(table));
+    // `(xmax = 0)` follows the table's columns.
+    let inserted = table.columns.len();
+    quote! {
+        use sqlx::{FromRow, Row};
+        let target: &str = #target;
+        let mut columns: Vec<&str> = Vec::new();
+        #(#present_columns)*
+        let mut set: Vec<String> = columns
+            .iter()
+            .filter(|column| !target.split(',').any(|t| t == **column))
+            .map(|column| format!("{0} = EXCLUDED.{0}", column))
+            .collect();
+        let touch: &[&str] = &[#(#touch),*];
+        set.extend(touch.iter().map(|s| s.to_string()));
+        if set.is_empty() {
+            // A no-op update still returns the existing row, DO NOTHING would not.
+            let first = target.split(',').next().unwrap_or_default();
+            set.push(format!("{0} = EXCLUDED.{0}", first));
+        }
+        #(columns.push(#audit_columns);)*
+        let mut query = sqlx::QueryBuilder::<sqlx::Postgres>::new(#insert_into);
+        query.push(columns.join(", "));
+        query.push(") VALUES (");
+        let mut values = query.separated(", ");
+        #(#present_values)*
+        #(values.push(#audit_values);)*
+        query.push(") ON CONFLICT (");
+        query.push(target);
+        query.push(") DO UPDATE SET ");
+        query.push(set.join(", "));
+        query.push(#returning);
+        let row = query.build().fetch_one(executor).await?;
+        Ok((#model::from_row(&row)?, row.try_get(#inserted)?))
+    }
+}
+
+// Server-maintained columns with their SQL values: the audit columns, see
+// `audit.rs`, and on update the ETag version, see `etag.rs`.
+struct Audit {
+    insert: Vec<(String, String)>,
+    update: Vec<(String, String)>,
+}
+
+pub fn repository(
+    table: &parse::Table,
+    schema: &parse::Schema,
+    config: &Config,
+) -> proc_macro2::TokenStream {
+    let names = Names::new(table, config);
+    let Names {
+        model,
+        new_model,
+        patch,
+        key,
+        repository,
+    } = &names;
+    let table_name = &table.name;
+    let cols = column_list(table);
+    let doc = format!(
+        "Queries against the `{}` table.",
+        table_name.trim_matches('"')
+    );
+
+    let primary_key = table.primary_key();
+    let key_columns: Vec<&parse::Column> = primary_key
+        .iter()
+        .filter_map(|name| table.column(name))
+        .collect();
+    let key_names: Vec<syn::Ident> = key_columns.iter().map(|c| field_ident(&c.name)).collect();
+
+    let select_all = format!("SELECT {} FROM {}", cols, table_name);
+    // Soft deleted rows are skipped by everything but the list queries, which
+    // handle `include_deleted` themselves.
+    let deleted = crate::soft_delete::column(table, config);
+    let alive = deleted.map_or(String::new(), |column| {
+        format!(" AND {} IS NULL", column.name)
+    });
+    let list_all = match deleted {
+        Some(column) => format!("{} WHERE {} IS NULL", select_all, column.name),
+        None => select_all.clone(),
+    };
+    let count = format!("SELECT COUNT(*) FROM {}", table_name);
+    let field_names = table.columns.iter().map(|c| json_name(&c.name));
+    let field_types = table.columns.iter().map(|col| {
+        let ty = types::column_type(table_name, col, schema, config);
+        if table.is_nullable(col) {
+            quote! { Option<#ty> }
+        } else {
+            quote! { #ty }
+        }
+    });
+
+    // Server-filled columns are not in the create model; optional ones are
+    // left out of the INSERT when absent, so a column default still applies.
+    let insert_columns = insert_columns(table, config);
+    let mut audit = Audit {
+        insert: crate::audit::on_insert(table, schema, config),
+        update: crate::audit::on_update(table, schema, config),
+    };
+    if let Some(column) = crate::etag::version_column(table, config) {
+        // Qualified, as `ON CONFLICT DO UPDATE` also sees `EXCLUDED`.
+        let increment = format!("{}.{} + 1", table_name, column.name);
+        audit.update.push((column.name.clone(), increment));
+    }
+    let (audit_columns, audit_values): (Vec<&String>, Vec<&String>) = audit
+        .insert
+        .iter()
+        .map(|(name, value)| (name, value))
+        .unzip();
+    let insert_default = format!(
+        "INSERT INTO {} DEFAULT VALUES RETURNING {}",
+        table_name, cols
+    );
+    let insert_into = format!("INSERT INTO {} (", table_name);
+    let insert_returning = format!(") RETURNING {}", cols);
+    let (present_columns, present_values): (Vec<_>, Vec<_>) = insert_columns
+        .iter()
+        .map(|col| {
+            let field = field_ident(&col.name);
+            let name = &col.name;
+            if is_optional(table, col) {
+                (
+                    quote! { if new.#field.is_some() { columns.push(#name); } },
+                    quote! { if let Some(value) = &new.#field { values.push_bind(value); } },
+                )
+            } else {
+                (
+                    quote! { columns.push(#name); },
+                    quote! { values.push_bind(&new.#field); },
+                )
+            }
+        })
+        .unzip();
+    // With audit columns there is always something to insert.
+    let default_values = if audit.insert.is_empty() {
+        quote! {
+            if columns.is_empty() {
+                return sqlx::query_as::<_, #model>(#insert_default).fetch_one(executor).await;
+            }
+        }
+    } else {
+        quote! {}
+    };
+    let insert = if insert_columns.is_empty() && audit.insert.is_empty() {
+        quote! { sqlx::query_as::<_, #model>(#insert_default).fetch_one(executor).await }
+    } else {
+        quote! {
+            let mut columns: Vec<&str> = Vec::new();
+            #(#present_columns)*
+            #(columns.push(#audit_columns);)*
+            #default_values
+            let mut query = sqlx::QueryBuilder::<sqlx::Postgres>::new(#insert_into);
+            query.push(columns.join(", "));
+            query.push(") VALUES (");
+            let mut values = query.separated(", ");
+            #(#present_values)*
+            #(values.push(#audit_values);)*
+            query.push(#insert_returning);
+            query.build_query_as::<#model>().fetch_one(executor).await
+        }
+    };
+
+    // Several rows in one statement list every column, with `DEFAULT` for
+    // absent optional fields. Postgres takes at most 65535 parameters per
+    // statement.
+    let insert_many = if insert_columns.is_empty() {
+        quote! {}
+    } else {
+        let insert_many = format!(
+            "INSERT INTO {} ({}) ",
+            table_name,
+            insert_columns
+                .iter()
+                .map(|c| &c.name)
+                .chain(audit_columns.iter().copied())
+                .map(|name| name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        let returning = format!(" RETURNING {}", cols);
+        let row_values = insert_columns.iter().map(|col| {
+            let field = field_ident(&col.name);
+            if is_optional(table, col) {
+                quote! {
+                    match &new.#field {
+                        Some(value) => values.push_bind(value),
+                        None => values.push("DEFAULT"),
+                    };
+                }
+            } else {
+                quote! { values.push_bind(&new.#field); }
+            }
+        });
+        let chunk = 65535 / insert_columns.len();
+        quote! {
+            /// Most rows `insert_many` takes at once.
+            pub const INSERT_CHUNK: usize = #chunk;
+
+            /// Inserts up to `INSERT_CHUNK` rows in one statement, returning them in order.
+            pub async fn insert_many<'e, E>(executor: E, rows: &[#new_model]) -> Result<Vec<#model>, sqlx::Error>
+            where
+                E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+            {
+                if rows.is_empty() {
+                    return Ok(Vec::new());
+                }
+                let mut query = sqlx::QueryBuilder::<sqlx::Postgres>::new(#insert_many);
+                query.push_values(rows, |mut values, new| {
+                    #(#row_values)*
+                    #(values.push(#audit_values);)*
+                });
+                query.push(#returning);
+                query.build_query_as::<#model>().fetch_all(executor).await
+            }
+        }
+    };
+    // `deleted_at = now(), updated_at = now()`, marking rows soft deleted.
+    let mark_deleted = deleted.map(|column| {
+        std::iter::once(format!("{} = now()", column.name))
+            .chain(
+                audit
+                    .update
+                    .iter()
+                    .map(|(name, value)| format!("{} = {}", name, value)),
+            )
+            .collect::<Vec<_>>()
+            .join(", ")
+    });
+    let delete_where = match &mark_deleted {
+        Some(set) => format!("UPDATE {} SET {}", table_name, set),
+        None => format!("DELETE FROM {}", table_name),
+    };
+
+    let targets = conflict_targets(table, config);
+    let upsert_on = if targets.is_empty() {
+        quote! {}
+    } else {
+        let defaulted: Vec<&parse::Column> = insert_columns
+            .iter()
+            .filter(|c| c.has_default())
+            .copied()
+            .collect();
+        let values: Vec<(&parse::Column, proc_macro2::TokenStream)> = insert_columns
+            .iter()
+            .map(|col| {
+                let field = field_ident(&col.name);
+                (*col, quote! { new.#field })
+            })
+            .collect();
+        let target_lists: Vec<String> = targets
+            .iter()
+            .map(|(_, columns)| columns.join(","))
+            .collect();
+        let names = targets
+            .iter()
+            .zip(&target_lists)
+            .map(|((name, _), list)| match name {
+                Some(name) => quote! { #name | #list },
+                None => quote! { #list },
+            });
+        let body = upsert_body(
+            table,
+            model,
+            &values,
+            &defaulted,
+            quote! {
+                match target {
+                    #(#target_lists => #target_lists,)*
+                    _ => return Err(sqlx::Error::InvalidArgument(format!("no unique key ({})", target))),
+                }
+            },
+            &audit,
+            deleted,
+        );
+        quote! {
+            /// Unique keys `upsert_on` can resolve a conflict on, as column lists.
+            pub const CONFLICT_TARGETS: &[&str] = &[#(#target_lists),*];
+
+            /// The conflict target named by a constraint or its columns, e.g. in `?on_conflict=`.
+            pub fn conflict_target(name: &str) -> Option<&'static str> {
+                match name {
+                    #(#names => Some(#target_lists),)*
+                    _ => None,
+                }
+            }
+
+            /// Inserts the row, or updates the one sharing its `target` unique key
+            /// (one of `CONFLICT_TARGETS`). Also returns whether the row was created.
+            pub async fn upsert_on<'e, E>(executor: E, new: &#new_model, target: &str) -> Result<(#model, bool), sqlx::Error>
+            where
+                E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+            {
+                #body
+            }
+        }
+    };
+
+    let by_key = if key_columns.is_empty() {
+        quote! {}
+    } else {
+        let select_any = format!(
+            "SELECT {} FROM {} WHERE {}",
+            cols,
+            table_name,
+            where_clause(&key_columns, 1)
+        );
+        let select = format!("{}{}", select_any, alive);
+        let lock = format!("{} FOR UPDATE", select);
+        let delete = match &mark_deleted {
+            Some(set) => format!(
+                "UPDATE {} SET {} WHERE {}{}",
+                table_name,
+                set,
+                where_clause(&key_columns, 1),
+                alive
+            ),
+            None => format!(
+                "DELETE FROM {} WHERE {}",
+                table_name,
+                where_clause(&key_columns, 1)
+            ),
+        };
+        // Key columns come from the key, everything else the client may set from `new`.
+        let new_columns: Vec<&parse::Column> = insert_columns
+            .iter()
+            .filter(|c| !table.is_primary_key(&c.name))
+            .copied()
+            .collect();
+        let values: Vec<(&parse::Column, proc_macro2::TokenStream)> = key_columns
+            .iter()
+            .map(|col| {
+                let field = field_ident(&col.name);
+                (*col, quote! { key.#field })
+            })
+            .chain(new_columns.iter().map(|col| {
+                let field = field_ident(&col.name);
+                (*col, quote! { new.#field })
+            }))
+            .collect();
+        let defaulted: Vec<&parse::Column> = new_columns
+            .iter()
+            .filter(|c| c.has_default())
+            .copied()
+            .collect();
+        let target = primary_key.join(",");
+        let upsert = upsert_body(
+            table,
+            model,
+            &values,
+            &defaulted,
+            quote! { #target },
+            &audit,
+            deleted,
+        );
+        let patch_columns: Vec<&parse::Column> = table
+            .columns
+            .iter()
+            .filter(|c| !table.is_primary_key(&c.name) && !is_managed(table, c, config))
+            .collect();
+        let update = if patch_columns.is_empty() {
+            // Nothing but key columns: there is nothing to change.
+            quote! { Self::get_by_pk(executor, key).await }
+        } else {
+            let update = format!("UPDATE {} SET ", table_name);
+            let assignments = patch_columns.iter().map(|col| {
+                let field = field_ident(&col.name);
+                let assign = format!("{} = ", col.name);
+                quote! {
+                    if let Some(value) = &patch.#field {
+                        set.push(#assign);
+                        set.push_bind_unseparated(value);
+                    }
+                }
+            });
+            let conditions = key_columns.iter().enumerate().map(|(i, col)| {
+                let field = field_ident(&col.name);
+                let condition = format!(
+                    "{}{} = ",
+                    if i == 0 { " WHERE " } else { " AND " },
+                    col.name
+                );
+                quote! {
+                    query.push(#condition);
+                    query.push_bind(&key.#field);
+                }
+            });
+            let returning = format!(" RETURNING {}", cols);
+            let touch = audit
+                .update
+                .iter()
+                .map(|(name, value)| format!("{} = {}", name, value));
+            quote! {
+                if patch.present_fields().is_empty() {
+                    return Self::get_by_pk(executor, key).await;
+                }
+                let mut query = sqlx::QueryBuilder::<sqlx::Postgres>::new(#update);
+                let mut set = query.separated(", ");
+                #(#assignments)*
+                #(set.push(#touch);)*
+                #(#conditions)*
+                query.push(#alive);
+                query.push(#returning);
+                query.build_query_as::<#model>().fetch_optional(executor).await
+            }
+        };
+        let soft_delete = match deleted {
+            Some(column) => {
+                let touch = audit
+                    .update
+                    .iter()
+                    .map(|(name, value)| format!(", {} = {}", name, value))
+                    .collect::<String>();
+                let restore = format!(
+                    "UPDATE {} SET {} = NULL{} WHERE {} RETURNING {}",
+                    table_name,
+                    column.name,
+                    touch,
+                    where_clause(&key_columns, 1),
+                    cols
+                );
+                quote! {
+                    /// The row with this primary key, if any, even if soft deleted.
+                    pub async fn get_by_pk_with_deleted<'e, E>(executor: E, key: &#key) -> Result<Option<#model>, sqlx::Error>
+                    where
+                        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+                    {
+                        sqlx::query_as::<_, #model>(#select_any)
+                            #(.bind(&key.#key_names))*
+                            .fetch_optional(executor)
+                            .await
+                    }
+
+                    /// Clears the soft delete mark and returns the row, or `None` when
+                    /// no row has this key.
+                    pub async fn restore<'e, E>(executor: E, key: &#key) -> Result<Option<#model>, sqlx::Error>
+                    where
+                        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+                    {
+                        sqlx::query_as::<_, #model>(#restore)
+                            #(.bind(&key.#key_names))*
+                            .fetch_optional(executor)
+                            .await
+                    }
+                }
+            }
+            None => quote! {},
+        };
+        let delete_doc = match deleted {
+            Some(column) => format!(
+                "Marks the row deleted by setting `{}`, returning whether there was one.",
+                column.name
+            ),
+            None => String::from("Deletes the row, returning whether there was one."),
+        };
+        quote! {
+            /// The row with this primary key, if any.
+            pub async fn get_by_pk<'e, E>(executor: E, key: &#key) -> Result<Option<#model>, sqlx::Error>
+            where
+                E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+            {
+                sqlx::query_as::<_, #model>(#select)
+                    #(.bind(&key.#key_names))*
+                    .fetch_optional(executor)
+                    .await
+            }
+
+            /// The row with this primary key, locked until the transaction ends.
+            pub async fn lock<'e, E>(executor: E, key: &#key) -> Result<Option<#model>, sqlx::Error>
+            where
+                E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+            {
+                sqlx::query_as::<_, #model>(#lock)
+                    #(.bind(&key.#key_names))*
+                    .fetch_optional(executor)
+                    .await
+            }
+
+            /// Sets the fields present in `patch` and returns the updated row,
+            /// or `None` when no row has this key.
+            pub async fn update<'e, E>(executor: E, key: &#key, patch: &#patch) -> Result<Option<#model>, sqlx::Error>
+            where
+                E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+            {
+                #update
+            }
+
+            /// Inserts the row under `key`, or replaces the client-settable fields
+            /// of the row already there. Also returns whether the row was created.
+            pub async fn upsert<'e, E>(executor: E, key: &#key, new: &#new_model) -> Result<(#model, bool), sqlx::Error>
+            where
+                E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+            {
+                #upsert
+            }
+
+            #[doc = #delete_doc]
+            pub async fn delete<'e, E>(executor: E, key: &#key) -> Result<bool, sqlx::Error>
+            where
+                E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+            {
+                let result = sqlx::query(#delete)
+                    #(.bind(&key.#key_names))*
+                    .execute(executor)
+                    .await?;
+                Ok(result.rows_affected() > 0)
+            }
+
+            #soft_delete
+        }
+    };
+
+    let finders: Vec<proc_macro2::TokenStream> = lookups(table)
+        .into_iter()
+        .map(|columns| {
+            let finder = syn::Ident::new(
+                &format!("find_by_{}", lookup_name(&columns)),
+                proc_macro2::Span::call_site(),
+            );
+            let fields: Vec<syn::Ident> = columns.iter().map(|c| field_ident(&c.name)).collect();
+            let tys = columns
+                .iter()
+                .map(|col| types::column_type(table_name, col, schema, config));
+            let select = format!(
+                "SELECT {} FROM {} WHERE {}{}",
+                cols,
+                table_name,
+                where_clause(&columns, 1),
+                alive
+            );
+            let doc = format!(
+                "The row with this `{}`, if any.",
+                columns
+                    .iter()
+                    .map(|c| c.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            quote! {
+                #[doc = #doc]
+                pub async fn #finder<'e, E>(executor: E, #(#fields: &#tys),*) -> Result<Option<#model>, sqlx::Error>
+                where
+                    E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+                {
+                    sqlx::query_as::<_, #model>(#select)
+                        #(.bind(#fields))*
+                        .fetch_optional(executor)
+                        .await
+                }
+            }
+        })
+        .collect();
+    let descriptor = crate::list::table(table, schema, config);
+
+    quote! {
+        #[doc = #doc]
+        pub struct #repository;
+
+        impl #repository {
+            #descriptor
+
+            pub async fn list<'e, E>(executor: E) -> Result<Vec<#model>, sqlx::Error>
+            where
+                E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+            {
+                sqlx::query_as::<_, #model>(#list_all).fetch_all(executor).await
+            }
+
+            /// The requested page of rows matching the filters of a list query.
+            /// `list.fields` is ignored, see [`search_fields`](Self::search_fields).
+            pub async fn search<'e, E>(executor: E, list: &list_query::ListQuery) -> Result<list_query::Page<#model>, sqlx::Error>
+            where
+                E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+            {
+                let mut query = sqlx::QueryBuilder::<sqlx::Postgres>::new(#select_all);
+                list.push_where(&mut query);
+                list.push_page(&mut query);
+                let items = query.build_query_as::<#model>().fetch_all(executor).await?;
+                Ok(list.page(items))
+            }
+
+            /// Like [`search`](Self::search), but selects only the columns in
+            /// `list.fields` (if given) and returns each row as a JSON object.
+            pub async fn search_fields<'e, E>(executor: E, list: &list_query::ListQuery) -> Result<list_query::Page<list_query::Object>, sqlx::Error>
+            where
+                E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+            {
+                let selected = list.selected();
+                let mut query = sqlx::QueryBuilder::<sqlx::Postgres>::new("");
+                list.push_select(&mut query);
+                list.push_where(&mut query);
+                list.push_page(&mut query);
+                let rows = query.build().fetch_all(executor).await?;
+                let items = rows
+                    .iter()
+                    .map(|row| Self::row_fields(row, &selected))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let mut page = list.page(items);
+                for item in &mut page.items {
+                    item.retain(|name, _| list.returns(name));
+                }
+                Ok(page)
+            }
+
+            // Decodes the selected columns of a row with the model's field types.
+            fn row_fields(
+                row: &sqlx::postgres::PgRow,
+                columns: &[&'static list_query::Column],
+            ) -> Result<list_query::Object, sqlx::Error> {
+                use sqlx::Row;
+                let mut fields = serde_json::Map::new();
+                for (i, column) in columns.iter().enumerate() {
+                    let value = match column.name {
+                        #(#field_names => serde_json::to_value(row.try_get::<#field_types, _>(i)?),)*
+                        _ => continue,
+                    };
+                    let value = value.map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+                    fields.insert(column.name.to_string(), value);
+                }
+                Ok(fields)
+            }
+
+            /// Number of rows matching the filters of a list query, ignoring paging.
+            pub async fn count<'e, E>(executor: E, list: &list_query::ListQuery) -> Result<i64, sqlx::Error>
+            where
+                E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+            {
+                let mut query = sqlx::QueryBuilder::<sqlx::Postgres>::new(#count);
+                list.push_where(&mut query);
+                query.build_query_scalar::<i64>().fetch_one(executor).await
+            }
+
+            /// Inserts the row and returns it with server-generated values filled in.
+            pub async fn insert<'e, E>(executor: E, new: &#new_model) -> Result<#model, sqlx::Error>
+            where
+                E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+            {
+                #insert
+            }
+
+            #insert_many
+
+            #upsert_on
+
+            /// Deletes (or on soft deleted tables, marks) the rows matching the
+            /// filters of a list query, returning how many.
+            pub async fn delete_where<'e, E>(executor: E, list: &list_query::ListQuery) -> Result<u64, sqlx::Error>
+            where
+                E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+            {
+                let mut query = sqlx::QueryBuilder::<sqlx::Postgres>::new(#delete_where);
+                list.push_where(&mut query);
+                Ok(query.build().execute(executor).await?.rows_affected())
+            }
+
+            #by_key
+
+            #(#finders)*
+        }
+    }
+}
@@ -0,0 +1,108 @@
+// Soft delete, for tables with a nullable `deleted_at` timestamp (or the
+// column named by the `soft_delete` option):
+//
+//     DELETE /assets/{asset_id}                 sets deleted_at = now(), 204
+//     GET    /assets, /assets/{asset_id}        deleted rows are left out
+//     GET    /assets?include_deleted=true       ... unless asked for
+//     POST   /assets/{asset_id}/restore         clears deleted_at, 200 with the row
+//
+// The column is maintained by the server like the audit columns, and marking
+// a row deleted also sets `updated_at` and `updated_by`. Updates and unique
+// key lookups do not see deleted rows, while a PUT to a deleted row's key
+// brings it back. `ON DELETE CASCADE` does not apply, as nothing is deleted.
+
+use crate::config::Config;
+use crate::repository::Names;
+use crate::types;
+use quote::quote;
+
+const DEFAULT: &str = "deleted_at";
+
+fn is_timestamp(column: &parse::Column) -> bool {
+    matches!(
+        types::normalize(&column.dtype).as_str(),
+        "timestamptz"
+            | "timestamp with time zone"
+            | "timestamp"
+            | "timestamp without time zone"
+            | "date"
+    )
+}
+
+/// The column marking deleted rows of `table`, if it is soft deleted.
+pub fn column<'a>(table: &'a parse::Table, config: &Config) -> Option<&'a parse::Column> {
+    let name = config.soft_delete_column(&table.name).unwrap_or(DEFAULT);
+    table
+        .column(name)
+        .filter(|column| table.is_nullable(column) && is_timestamp(column))
+}
+
+/// Whether `column` is the soft delete column of `table`.
+pub fn is_column(table: &parse::Table, column: &parse::Column, config: &Config) -> bool {
+    self::column(table, config).is_some_and(|c| c.name == column.name)
+}
+
+/// Every `soft_delete` entry must name a nullable timestamp column of a table.
+pub fn validate(schema: &parse::Schema, config: &Config) -> syn::Result<()> {
+    for (table, name) in &config.soft_delete {
+        let error = |message: String| Err(syn::Error::new(proc_macro2::Span::call_site(), message));
+        let Some(found) = schema
+            .tables
+            .iter()
+            .find(|t| t.name.trim_matches('"').eq_ignore_ascii_case(table))
+        else {
+            return error(format!("soft_delete: unknown table `{}`", table));
+        };
+        let Some(column) = found.column(name) else {
+            return error(format!("soft_delete: `{}` has no column `{}`", table, name));
+        };
+        if !found.is_nullable(column) || !is_timestamp(column) {
+            return error(format!(
+                "soft_delete: `{}.{}` must be a nullable timestamp or date",
+                table, name
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// `POST /<table>/{key}/restore` for a soft deleted table with a primary key.
+pub fn routes(
+    table: &parse::Table,
+    key_path: &str,
+    config: &Config,
+) -> (proc_macro2::TokenStream, Vec<syn::Ident>) {
+    if column(table, config).is_none() {
+        return (quote! {}, Vec::new());
+    }
+    let Names {
+        key, repository, ..
+    } = &Names::new(table, config);
+    let route = format!("/{}/{}/restore", table.name, key_path);
+    let not_found = format!("{} not found", table.name);
+    let handler = syn::Ident::new(
+        &format!(
+            "restore_{}_handler",
+            table.name.trim_matches('"').to_lowercase()
+        ),
+        proc_macro2::Span::call_site(),
+    );
+    let output = quote! {
+        #[post(#route)]
+        async fn #handler(
+            req: actix_web::HttpRequest,
+            path: web::Path<#key>,
+            pool: web::Data<PgPool>,
+        ) -> Result<HttpResponse, ApiError> {
+            let mut tx = audit::begin(&pool, &req).await?;
+            let Some(row) = #repository::restore(&mut *tx, &path).await? else {
+                return Err(ApiError::not_found(#not_found));
+            };
+            tx.commit().await?;
+            Ok(HttpResponse::Ok()
+                .insert_header((actix_web::http::header::ETAG, row.etag()))
+                .json(row))
+        }
+    };
+    (output, vec![handler])
+}
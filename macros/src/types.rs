@@ -0,0 +1,398 @@
+// Postgres to Rust type mapping for `generate_structs_from_ddl`.
+//
+// Date and time columns map to `chrono` by default; enabling the `time`
+// feature of this crate switches them to the `time` crate instead.
+//
+// `interval`, `money`, `timetz` and the range types map to sqlx's `PgInterval`,
+// `PgMoney`, `PgTimeTz` and `PgRange`, wrapped in the generated `pg_types`
+// module because those have no serde support:
+//
+//     interval    pg_types::Interval   {"months": 1, "days": 2, "microseconds": 0}
+//     money       pg_types::Money      1050, in the currency's minor unit
+//     timetz      pg_types::TimeTz     {"time": "12:00:00", "offset_seconds": 3600}
+//     int4range   pg_types::Range<i32> {"start": {"Included": 1}, "end": "Unbounded"}
+//
+// Any other type without a mapping (bit, tsvector, xml, ...) is a compile
+// error naming the column, unless the config overrides it.
+
+use crate::config::Config;
+use quote::quote;
+
+// "timestamp(3) with time zone" -> "timestamp with time zone"
+fn strip_modifiers(dtype: &str) -> String {
+    let mut out = String::new();
+    let mut depth = 0;
+    for c in dtype.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ if depth == 0 => out.push(c),
+            _ => {}
+        }
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn scalar_type_name(dtype: &str) -> Option<&'static str> {
+    let time = cfg!(feature = "time");
+    let name = match dtype {
+        "bool" | "boolean" => "bool",
+        "\"char\"" => "i8",
+        "int2" | "smallint" | "smallserial" | "serial2" => "i16",
+        "int" | "int4" | "integer" | "serial" | "serial4" => "i32",
+        "int8" | "bigint" | "bigserial" | "serial8" => "i64",
+        "oid" => "sqlx::postgres::types::Oid",
+        "float4" | "real" => "f32",
+        "float8" | "float" | "double precision" => "f64",
+        "numeric" | "decimal" => "rust_decimal::Decimal",
+        "text" | "varchar" | "character varying" | "char" | "character" | "bpchar" | "name"
+        | "citext" => "String",
+        "bytea" => "Vec<u8>",
+        "uuid" => "uuid::Uuid",
+        "json" | "jsonb" => "serde_json::Value",
+        "inet" | "cidr" => "ipnetwork::IpNetwork",
+        "macaddr" => "mac_address::MacAddress",
+        "date" if time => "time::Date",
+        "date" => "chrono::NaiveDate",
+        "time" | "time without time zone" if time => "time::Time",
+        "time" | "time without time zone" => "chrono::NaiveTime",
+        "timestamp" | "timestamp without time zone" if time => "time::PrimitiveDateTime",
+        "timestamp" | "timestamp without time zone" => "chrono::NaiveDateTime",
+        "timestamptz" | "timestamp with time zone" if time => "time::OffsetDateTime",
+        "timestamptz" | "timestamp with time zone" => "chrono::DateTime<chrono::Utc>",
+        "timetz" | "time with time zone" => "pg_types::TimeTz",
+        "interval" => "pg_types::Interval",
+        "money" => "pg_types::Money",
+        "int4range" => "pg_types::Range<i32>",
+        "int8range" => "pg_types::Range<i64>",
+        "numrange" => "pg_types::Range<rust_decimal::Decimal>",
+        "daterange" if time => "pg_types::Range<time::Date>",
+        "daterange" => "pg_types::Range<chrono::NaiveDate>",
+        "tsrange" if time => "pg_types::Range<time::PrimitiveDateTime>",
+        "tsrange" => "pg_types::Range<chrono::NaiveDateTime>",
+        "tstzrange" if time => "pg_types::Range<time::OffsetDateTime>",
+        "tstzrange" => "pg_types::Range<chrono::DateTime<chrono::Utc>>",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Lower-cased SQL type without length/precision modifiers.
+pub fn normalize(dtype: &str) -> String {
+    strip_modifiers(&dtype.trim().to_lowercase())
+}
+
+/// Rust type for a SQL type as written in DDL (`varchar(20)`, `text[]`) or as
+/// reported by `information_schema` (`_text`, `int8`). Matching is
+/// case-insensitive and user overrides from [`Config`] win over the defaults;
+/// `None` means the type is not known.
+pub fn resolve(dtype: &str, schema: &parse::Schema, config: &Config) -> Option<syn::Type> {
+    let normalized = normalize(dtype);
+    if let Some(ty) = config.type_override(&normalized) {
+        return Some(ty.clone());
+    }
+    if let Some(element) = normalized.strip_suffix("[]") {
+        let inner = resolve(element.trim_end_matches("[]"), schema, config)?;
+        return Some(syn::parse_quote!(Vec<#inner>));
+    }
+    if let Some(element) = normalized.strip_prefix('_') {
+        if let Some(inner) = resolve(element, schema, config) {
+            return Some(syn::parse_quote!(Vec<#inner>));
+        }
+    }
+    if let Some(enum_type) = schema.enum_type(&normalized) {
+        let ident = enum_ident(&enum_type.name);
+        return Some(syn::parse_quote!(#ident));
+    }
+    // `float(p)` is a `real` up to 24 binary digits of precision.
+    if normalized == "float" && float_precision(dtype).is_some_and(|p| p <= 24) {
+        return Some(syn::parse_quote!(f32));
+    }
+    scalar_type_name(&normalized).map(|name| syn::parse_str(name).expect("Invalid type"))
+}
+
+// "float(10)" -> 10
+fn float_precision(dtype: &str) -> Option<u32> {
+    let (_, args) = dtype.split_once('(')?;
+    args.split(')').next()?.trim().parse().ok()
+}
+
+fn try_column_type(
+    table: &str,
+    column: &parse::Column,
+    schema: &parse::Schema,
+    config: &Config,
+) -> Option<syn::Type> {
+    match config.column_override(table, &column.name) {
+        Some(ty) => Some(ty.clone()),
+        None => resolve(&column.dtype, schema, config),
+    }
+}
+
+/// Rust type for a column: a `table.column` override, then the SQL type
+/// mapping. Columns with neither are rejected by [`validate`].
+pub fn column_type(
+    table: &str,
+    column: &parse::Column,
+    schema: &parse::Schema,
+    config: &Config,
+) -> syn::Type {
+    try_column_type(table, column, schema, config).expect("column types are validated")
+}
+
+/// Every column must have a Rust type.
+pub fn validate(schema: &parse::Schema, config: &Config) -> syn::Result<()> {
+    for table in &schema.tables {
+        for column in &table.columns {
+            if try_column_type(&table.name, column, schema, config).is_none() {
+                return Err(syn::Error::new(
+                    proc_macro2::Span::call_site(),
+                    format!(
+                        "column `{}.{}` has the type `{}`, which has no Rust mapping; map it in `types` or `columns`",
+                        table.name.trim_matches('"'),
+                        column.name.trim_matches('"'),
+                        column.dtype
+                    ),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+// asset_status -> AssetStatus
+pub fn camel_case(name: &str) -> String {
+    let camel: String = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect();
+    if camel.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("V{}", camel)
+    } else {
+        camel
+    }
+}
+
+// assets -> asset, categories -> category, addresses -> address. Words that
+// only look plural (status, class, news) are kept; `models` in the config
+// covers the rest.
+fn singular(name: &str) -> String {
+    if let Some(stem) = name.strip_suffix("ies") {
+        return format!("{}y", stem);
+    }
+    if let Some(stem) = name.strip_suffix("sses") {
+        return format!("{}ss", stem);
+    }
+    for suffix in ["xes", "ches", "shes"] {
+        if let Some(stem) = name.strip_suffix(suffix) {
+            return format!("{}{}", stem, &suffix[..suffix.len() - 2]);
+        }
+    }
+    match name.strip_suffix('s') {
+        Some(stem) if !stem.ends_with(['s', 'u', 'w']) && !stem.is_empty() => stem.to_string(),
+        _ => name.to_string(),
+    }
+}
+
+/// Name of the row struct for a table: `asset_types` -> `AssetType`, unless
+/// `models` in the config names it.
+pub fn model_name(table: &str, config: &Config) -> String {
+    match config.model_name(table) {
+        Some(name) => name.to_string(),
+        None => camel_case(&singular(&table.trim_matches('"').to_lowercase())),
+    }
+}
+
+fn enum_ident(name: &str) -> syn::Ident {
+    syn::Ident::new(
+        &camel_case(name.trim_matches('"')),
+        proc_macro2::Span::call_site(),
+    )
+}
+
+/// A Rust enum mirroring a Postgres `CREATE TYPE ... AS ENUM`.
+pub fn enum_definition(enum_type: &parse::Enum) -> proc_macro2::TokenStream {
+    let ident = enum_ident(&enum_type.name);
+    let type_name = enum_type.name.trim_matches('"');
+    let variants = enum_type.values.iter().map(|value| {
+        let variant = syn::Ident::new(&camel_case(value), proc_macro2::Span::call_site());
+        quote! {
+            #[sqlx(rename = #value)]
+            #[serde(rename = #value)]
+            #variant,
+        }
+    });
+    quote! {
+        #[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+        #[sqlx(type_name = #type_name)]
+        pub enum #ident {
+            #(#variants)*
+        }
+    }
+}
+
+/// `pub mod pg_types`, serde-capable wrappers around the sqlx types that
+/// columns map to, with only the ones some column uses.
+pub fn support(schema: &parse::Schema, config: &Config) -> proc_macro2::TokenStream {
+    let used: Vec<String> = schema
+        .tables
+        .iter()
+        .flat_map(|table| {
+            table.columns.iter().filter_map(|column| {
+                let ty = try_column_type(&table.name, column, schema, config)?;
+                Some(quote!(#ty).to_string().replace(' ', ""))
+            })
+        })
+        .collect();
+    let uses = |name: &str| {
+        let name = format!("pg_types::{}", name);
+        used.iter().any(|ty| ty.contains(&name))
+    };
+    let mut wrappers = proc_macro2::TokenStream::new();
+    if uses("Interval") {
+        wrappers.extend(quote! {
+            /// An `interval`, as its months, days and microseconds.
+            #[derive(Debug, Clone, PartialEq, sqlx::Type)]
+            #[sqlx(transparent)]
+            pub struct Interval(pub sqlx::postgres::types::PgInterval);
+
+            #[derive(serde::Serialize, serde::Deserialize)]
+            struct IntervalParts {
+                months: i32,
+                days: i32,
+                microseconds: i64,
+            }
+
+            impl serde::Serialize for Interval {
+                fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    let sqlx::postgres::types::PgInterval { months, days, microseconds } = self.0;
+                    serde::Serialize::serialize(&IntervalParts { months, days, microseconds }, serializer)
+                }
+            }
+
+            impl<'de> serde::Deserialize<'de> for Interval {
+                fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    let IntervalParts { months, days, microseconds } = serde::Deserialize::deserialize(deserializer)?;
+                    Ok(Interval(sqlx::postgres::types::PgInterval { months, days, microseconds }))
+                }
+            }
+        });
+    }
+    if uses("Money") {
+        wrappers.extend(quote! {
+            /// A `money` amount in the currency's minor unit, e.g. cents.
+            #[derive(Debug, Clone, PartialEq, sqlx::Type)]
+            #[sqlx(transparent)]
+            pub struct Money(pub sqlx::postgres::types::PgMoney);
+
+            impl serde::Serialize for Money {
+                fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    serializer.serialize_i64(self.0 .0)
+                }
+            }
+
+            impl<'de> serde::Deserialize<'de> for Money {
+                fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    <i64 as serde::Deserialize>::deserialize(deserializer).map(|amount| Money(sqlx::postgres::types::PgMoney(amount)))
+                }
+            }
+        });
+    }
+    if uses("TimeTz") {
+        let (time, offset, seconds, from_seconds) = if cfg!(feature = "time") {
+            (
+                quote!(time::Time),
+                quote!(time::UtcOffset),
+                quote!(self.0.offset.whole_seconds()),
+                quote!(time::UtcOffset::from_whole_seconds(offset_seconds)
+                    .map_err(D::Error::custom)?),
+            )
+        } else {
+            (
+                quote!(chrono::NaiveTime),
+                quote!(chrono::FixedOffset),
+                quote!(self.0.offset.local_minus_utc()),
+                quote!(chrono::FixedOffset::east_opt(offset_seconds)
+                    .ok_or_else(|| D::Error::custom("offset out of range"))?),
+            )
+        };
+        wrappers.extend(quote! {
+            /// A `timetz`, as the time and its UTC offset in seconds.
+            #[derive(Debug, Clone, PartialEq, sqlx::Type)]
+            #[sqlx(transparent)]
+            pub struct TimeTz(pub sqlx::postgres::types::PgTimeTz<#time, #offset>);
+
+            #[derive(serde::Serialize, serde::Deserialize)]
+            struct TimeTzParts {
+                time: #time,
+                offset_seconds: i32,
+            }
+
+            impl serde::Serialize for TimeTz {
+                fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    let parts = TimeTzParts { time: self.0.time, offset_seconds: #seconds };
+                    serde::Serialize::serialize(&parts, serializer)
+                }
+            }
+
+            impl<'de> serde::Deserialize<'de> for TimeTz {
+                fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    use serde::de::Error;
+                    let TimeTzParts { time, offset_seconds } = serde::Deserialize::deserialize(deserializer)?;
+                    let offset = #from_seconds;
+                    Ok(TimeTz(sqlx::postgres::types::PgTimeTz { time, offset }))
+                }
+            }
+        });
+    }
+    if uses("Range") {
+        wrappers.extend(quote! {
+            /// A range, as its `start` and `end` bounds: `{"Included": 1}`,
+            /// `{"Excluded": 5}` or `"Unbounded"`.
+            #[derive(Debug, Clone, PartialEq, sqlx::Type)]
+            #[sqlx(transparent)]
+            pub struct Range<T>(pub sqlx::postgres::types::PgRange<T>);
+
+            #[derive(serde::Serialize)]
+            struct BoundsRef<'a, T> {
+                start: std::ops::Bound<&'a T>,
+                end: std::ops::Bound<&'a T>,
+            }
+
+            #[derive(serde::Deserialize)]
+            struct Bounds<T> {
+                start: std::ops::Bound<T>,
+                end: std::ops::Bound<T>,
+            }
+
+            impl<T: serde::Serialize> serde::Serialize for Range<T> {
+                fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    let bounds = BoundsRef { start: self.0.start.as_ref(), end: self.0.end.as_ref() };
+                    serde::Serialize::serialize(&bounds, serializer)
+                }
+            }
+
+            impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for Range<T> {
+                fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    let Bounds { start, end } = serde::Deserialize::deserialize(deserializer)?;
+                    Ok(Range(sqlx::postgres::types::PgRange { start, end }))
+                }
+            }
+        });
+    }
+    if wrappers.is_empty() {
+        return wrappers;
+    }
+    quote! {
+        pub mod pg_types {
+            #wrappers
+        }
+    }
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use actix_web::test::{call_and_read_body_json, call_service, init_service, TestRequest};
+use actix_web::{delete, get, patch, post, web, App, HttpResponse};
+use macros::generate_structs_from_ddl;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgPool;
+
+generate_structs_from_ddl!("tests/models.sql");
+
+const SCHEMA: &str = "batch_test";
+
+async fn count(pool: &PgPool, table: &str) -> i64 {
+    sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table))
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[actix_web::test]
+#[ignore = "needs DATABASE_URL"]
+async fn batch_runs_operations_in_one_transaction() {
+    let pool = common::test_pool(SCHEMA).await;
+    let app = init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .configure(configure),
+    )
+    .await;
+
+    // Later operations refer to the rows created earlier.
+    let req = TestRequest::post()
+        .uri("/batch")
+        .set_json(serde_json::json!([
+            { "op": "create", "table": "assets", "ref": "laptop",
+              "body": { "asset_name": "laptop" } },
+            { "op": "create", "table": "collections", "ref": "office",
+              "body": { "collection_name": "office" } },
+            { "op": "create", "table": "asset_collection",
+              "body": { "asset_id": { "$ref": "laptop.asset_id" },
+                        "collection_id": { "$ref": "office.collection_id" } } },
+            { "op": "update", "table": "collections",
+              "key": { "collection_id": { "$ref": "office.collection_id" } },
+              "body": { "collection_name": "home" } },
+        ]))
+        .to_request();
+    let outcomes: Vec<serde_json::Value> = call_and_read_body_json(&app, req).await;
+    let statuses: Vec<u64> = outcomes
+        .iter()
+        .map(|o| o["status"].as_u64().unwrap())
+        .collect();
+    assert_eq!(statuses, [201, 201, 201, 200]);
+    assert_eq!(
+        outcomes[2]["body"]["asset_id"],
+        outcomes[0]["body"]["asset_id"]
+    );
+    assert_eq!(outcomes[3]["body"]["collection_name"], "home");
+    let asset_id = outcomes[0]["body"]["asset_id"].clone();
+
+    // The second operation fails, so the first is rolled back.
+    let req = TestRequest::post()
+        .uri("/batch")
+        .set_json(serde_json::json!([
+            { "op": "create", "table": "assets", "body": { "asset_name": "phone" } },
+            { "op": "create", "table": "assets", "body": { "asset_name": "laptop" } },
+            { "op": "delete", "table": "assets", "key": { "asset_id": asset_id } },
+        ]))
+        .to_request();
+    let res = call_service(&app, req).await;
+    assert_eq!(res.status(), 409);
+    let problem: serde_json::Value = actix_web::test::read_body_json(res).await;
+    assert_eq!(problem["errors"][0]["index"], 1);
+    assert_eq!(count(&pool, "assets").await, 1);
+
+    for operations in [
+        serde_json::json!([{ "op": "create", "table": "nope", "body": {} }]),
+        serde_json::json!([{ "op": "create", "table": "assets",
+                             "body": { "asset_name": { "$ref": "missing.asset_name" } } }]),
+        serde_json::json!([{ "op": "upsert", "table": "assets" }]),
+    ] {
+        let req = TestRequest::post()
+            .uri("/batch")
+            .set_json(&operations)
+            .to_request();
+        assert_eq!(
+            call_service(&app, req).await.status(),
+            400,
+            "{}",
+            operations
+        );
+    }
+
+    let req = TestRequest::post()
+        .uri("/batch")
+        .set_json(serde_json::json!([
+            { "op": "delete", "table": "assets", "key": { "asset_id": asset_id } },
+        ]))
+        .to_request();
+    let outcomes: Vec<serde_json::Value> = call_and_read_body_json(&app, req).await;
+    assert_eq!(outcomes, [serde_json::json!({ "status": 204 })]);
+    assert_eq!(count(&pool, "asset_collection").await, 0);
+
+    common::drop_schema(&pool, SCHEMA).await;
+}
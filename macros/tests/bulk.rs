@@ -0,0 +1,142 @@
+mod common;
+
+use actix_web::test::{call_and_read_body_json, call_service, init_service, TestRequest};
+use actix_web::{delete, get, patch, post, web, App, HttpResponse};
+use macros::generate_structs_from_ddl;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgPool;
+
+generate_structs_from_ddl!("tests/models.sql", max_batch_size = 3);
+
+const SCHEMA: &str = "bulk_test";
+
+async fn asset_names(pool: &PgPool) -> Vec<String> {
+    sqlx::query_scalar("SELECT asset_name FROM assets ORDER BY asset_name")
+        .fetch_all(pool)
+        .await
+        .unwrap()
+}
+
+#[actix_web::test]
+#[ignore = "needs DATABASE_URL"]
+async fn bulk_endpoints_run_in_one_transaction() {
+    let pool = common::test_pool(SCHEMA).await;
+    sqlx::raw_sql("INSERT INTO brands VALUES ('ibm')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let app = init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .configure(configure),
+    )
+    .await;
+
+    let req = TestRequest::post()
+        .uri("/assets/bulk")
+        .set_json(serde_json::json!([
+            { "asset_name": "a", "asset_brand": "ibm" },
+            { "asset_name": "b" },
+        ]))
+        .to_request();
+    let res = call_service(&app, req).await;
+    assert_eq!(res.status(), 201);
+    let created: Vec<Asset> = actix_web::test::read_body_json(res).await;
+    assert_eq!(created.len(), 2);
+    assert_eq!(created[0].asset_brand.as_deref(), Some("ibm"));
+    assert_eq!(created[1].asset_name, "b");
+
+    // Absent defaulted columns take their default, supplied ones are kept.
+    // The ETag version is the server's, so a supplied one is ignored.
+    let id = uuid::Uuid::from_u128(7);
+    let req = TestRequest::post()
+        .uri("/collections/bulk")
+        .set_json(serde_json::json!([
+            { "collection_name": "x" },
+            { "collection_name": "y", "collection_id": id, "version": 3 },
+        ]))
+        .to_request();
+    let res = call_service(&app, req).await;
+    assert_eq!(res.status(), 201);
+    let collections: Vec<Collection> = actix_web::test::read_body_json(res).await;
+    assert_ne!(collections[0].collection_id, id);
+    assert_eq!(collections[1].collection_id, id);
+    let versions: Vec<i32> = collections.iter().map(|c| c.version).collect();
+    assert_eq!(versions, [1, 1]);
+
+    // Every failing row is reported and none of the others are kept.
+    let req = TestRequest::post()
+        .uri("/assets/bulk")
+        .set_json(serde_json::json!([
+            { "asset_name": "c" },
+            { "asset_name": "a" },
+            { "asset_name": "d", "asset_brand": "nope" },
+        ]))
+        .to_request();
+    let res = call_service(&app, req).await;
+    assert_eq!(res.status(), 422);
+    let problem: serde_json::Value = actix_web::test::read_body_json(res).await;
+    assert_eq!(problem["errors"][0]["index"], 1);
+    assert_eq!(problem["errors"][0]["status"], 409);
+    assert_eq!(problem["errors"][1]["index"], 2);
+    assert_eq!(problem["errors"][1]["status"], 422);
+    assert_eq!(asset_names(&pool).await, ["a", "b"]);
+
+    let req = TestRequest::post()
+        .uri("/assets/bulk")
+        .set_json(serde_json::json!([
+            { "asset_name": "w" },
+            { "asset_name": "x" },
+            { "asset_name": "y" },
+            { "asset_name": "z" },
+        ]))
+        .to_request();
+    assert_eq!(call_service(&app, req).await.status(), 413);
+
+    let req = TestRequest::patch()
+        .uri("/assets/bulk")
+        .set_json(serde_json::json!([
+            { "asset_id": created[0].asset_id, "asset_brand": null },
+            { "asset_id": created[1].asset_id, "asset_name": "c" },
+        ]))
+        .to_request();
+    let updated: Vec<Asset> = call_and_read_body_json(&app, req).await;
+    assert_eq!(updated[0].asset_name, "a");
+    assert_eq!(updated[0].asset_brand, None);
+    assert_eq!(updated[1].asset_name, "c");
+
+    let req = TestRequest::patch()
+        .uri("/assets/bulk")
+        .set_json(serde_json::json!([
+            { "asset_id": created[0].asset_id, "asset_name": "e" },
+            { "asset_id": uuid::Uuid::nil(), "asset_name": "f" },
+        ]))
+        .to_request();
+    let res = call_service(&app, req).await;
+    assert_eq!(res.status(), 422);
+    let problem: serde_json::Value = actix_web::test::read_body_json(res).await;
+    assert_eq!(problem["errors"][0]["index"], 1);
+    assert_eq!(problem["errors"][0]["status"], 404);
+    assert_eq!(asset_names(&pool).await, ["a", "c"]);
+
+    // Deleting needs a filter, and at most `max_batch_size` rows may match.
+    let req = TestRequest::delete().uri("/assets").to_request();
+    assert_eq!(call_service(&app, req).await.status(), 400);
+    sqlx::raw_sql("INSERT INTO assets (asset_name) VALUES ('g'), ('h'), ('i')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let req = TestRequest::delete()
+        .uri("/assets?asset_name[ne]=a")
+        .to_request();
+    assert_eq!(call_service(&app, req).await.status(), 422);
+    assert_eq!(asset_names(&pool).await.len(), 5);
+    let req = TestRequest::delete()
+        .uri("/assets?asset_name[in]=a,g,h")
+        .to_request();
+    let deleted: serde_json::Value = call_and_read_body_json(&app, req).await;
+    assert_eq!(deleted["deleted"], 3);
+    assert_eq!(asset_names(&pool).await, ["c", "i"]);
+
+    common::drop_schema(&pool, SCHEMA).await;
+}
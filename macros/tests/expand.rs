@@ -0,0 +1,96 @@
+mod common;
+
+use actix_web::test::{call_and_read_body_json, call_service, init_service, TestRequest};
+use actix_web::{delete, get, patch, post, web, App, HttpResponse};
+use macros::generate_structs_from_ddl;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::postgres::PgPool;
+
+generate_structs_from_ddl!("tests/models.sql", max_expand_depth = 2);
+
+const SCHEMA: &str = "expand_test";
+
+#[test]
+fn expand_follows_known_foreign_keys_up_to_the_maximum_depth() {
+    let expand = list_query::parse_expand(
+        "asset_id.asset_brand,collection_id,asset_id",
+        &AssetCollectionRepository::TABLE,
+    )
+    .unwrap();
+    assert_eq!(expand.len(), 2);
+    assert_eq!(expand[0].reference.name, "asset_id");
+    assert_eq!(expand[0].children[0].reference.name, "asset_brand");
+
+    for value in ["position", "asset_id.nope", "asset_id.asset_brand.brand_id"] {
+        let err = list_query::parse_expand(value, &AssetCollectionRepository::TABLE).unwrap_err();
+        assert_eq!(err.status, 400, "{}", value);
+    }
+}
+
+#[actix_web::test]
+#[ignore = "needs DATABASE_URL"]
+async fn expand_embeds_referenced_rows() {
+    let pool = common::test_pool(SCHEMA).await;
+    sqlx::raw_sql(
+        "INSERT INTO brands VALUES ('ibm');
+         INSERT INTO assets (asset_id, asset_name, asset_brand) VALUES
+             ('00000000-0000-0000-0000-000000000001', 'laptop', 'ibm'),
+             ('00000000-0000-0000-0000-000000000002', 'tablet', NULL);
+         INSERT INTO collections (collection_id, collection_name)
+             VALUES ('00000000-0000-0000-0000-00000000000c', 'office');
+         INSERT INTO asset_collection (asset_id, collection_id) VALUES
+             ('00000000-0000-0000-0000-000000000001', '00000000-0000-0000-0000-00000000000c'),
+             ('00000000-0000-0000-0000-000000000002', '00000000-0000-0000-0000-00000000000c');",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let app = init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .configure(configure),
+    )
+    .await;
+
+    let req = TestRequest::get()
+        .uri("/assets?expand=asset_brand&fields=asset_name&sort=asset_name")
+        .to_request();
+    let rows: serde_json::Value = call_and_read_body_json(&app, req).await;
+    assert_eq!(
+        rows,
+        json!([
+            { "asset_name": "laptop", "asset_brand": { "brand_id": "ibm" } },
+            { "asset_name": "tablet", "asset_brand": null },
+        ])
+    );
+
+    let req = TestRequest::get()
+        .uri("/asset_collection?expand=asset_id.asset_brand,collection_id&sort=asset_id")
+        .to_request();
+    let rows: serde_json::Value = call_and_read_body_json(&app, req).await;
+    assert_eq!(rows[0]["asset_id"]["asset_name"], "laptop");
+    assert_eq!(
+        rows[0]["asset_id"]["asset_brand"],
+        json!({ "brand_id": "ibm" })
+    );
+    assert_eq!(rows[1]["asset_id"]["asset_brand"], json!(null));
+    assert_eq!(rows[1]["collection_id"]["collection_name"], "office");
+
+    let req = TestRequest::get()
+        .uri("/assets/00000000-0000-0000-0000-000000000001?expand=asset_brand")
+        .to_request();
+    let row: serde_json::Value = call_and_read_body_json(&app, req).await;
+    assert_eq!(row["asset_name"], "laptop");
+    assert_eq!(row["asset_brand"], json!({ "brand_id": "ibm" }));
+
+    let req = TestRequest::get()
+        .uri("/assets?expand=asset_name")
+        .to_request();
+    let resp = call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+    let problem: serde_json::Value = actix_web::test::read_body_json(resp).await;
+    assert!(problem["detail"].as_str().unwrap().contains("asset_brand"));
+
+    common::drop_schema(&pool, SCHEMA).await;
+}
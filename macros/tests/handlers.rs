@@ -0,0 +1,647 @@
+mod common;
+
+use actix_web::{delete, get, patch, post, test, web, App, HttpResponse};
+use macros::generate_structs_from_ddl;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgPool;
+
+generate_structs_from_ddl!("tests/models.sql");
+
+const SCHEMA: &str = "handlers_test";
+
+#[actix_web::test]
+#[ignore = "needs DATABASE_URL"]
+async fn handlers_return_status_codes_and_problem_details() {
+    let pool = common::test_pool(SCHEMA).await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .configure(configure),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/assets")
+        .set_json(serde_json::json!({ "asset_name": "laptop" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 201);
+    let location = resp
+        .headers()
+        .get("location")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+    let created: Asset = test::read_body_json(resp).await;
+    assert_eq!(location, format!("/assets/{}", created.asset_id));
+
+    let req = test::TestRequest::get().uri(&location).to_request();
+    let fetched: Asset = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(fetched.asset_name, "laptop");
+
+    // Unique violation.
+    let req = test::TestRequest::post()
+        .uri("/assets")
+        .set_json(serde_json::json!({ "asset_name": "laptop" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 409);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/problem+json"
+    );
+    let problem: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(problem["status"], 409);
+    assert_eq!(problem["title"], "Conflict");
+
+    // Foreign key violation.
+    let req = test::TestRequest::post()
+        .uri("/assets")
+        .set_json(serde_json::json!({ "asset_name": "phone", "asset_brand": "nope" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 422);
+
+    let req = test::TestRequest::delete().uri(&location).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 204);
+    let req = test::TestRequest::delete().uri(&location).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 404);
+    let req = test::TestRequest::get().uri(&location).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 404);
+
+    common::drop_schema(&pool, SCHEMA).await;
+}
+
+#[actix_web::test]
+#[ignore = "needs DATABASE_URL"]
+async fn patch_updates_only_the_fields_in_the_body() {
+    let schema = "handlers_patch_test";
+    let pool = common::test_pool(schema).await;
+    sqlx::raw_sql("INSERT INTO brands VALUES ('ibm')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .configure(configure),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/assets")
+        .set_json(serde_json::json!({ "asset_name": "laptop", "asset_brand": "ibm" }))
+        .to_request();
+    let created: Asset = test::call_and_read_body_json(&app, req).await;
+    let uri = format!("/assets/{}", created.asset_id);
+
+    let req = test::TestRequest::patch()
+        .uri(&uri)
+        .set_json(serde_json::json!({ "asset_name": "desktop" }))
+        .to_request();
+    let updated: Asset = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(updated.asset_name, "desktop");
+    assert_eq!(updated.asset_brand.as_deref(), Some("ibm"));
+    assert_eq!(updated.created_at, created.created_at);
+
+    let req = test::TestRequest::patch()
+        .uri(&uri)
+        .set_json(serde_json::json!({ "asset_brand": null }))
+        .to_request();
+    let updated: Asset = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(updated.asset_name, "desktop");
+    assert_eq!(updated.asset_brand, None);
+
+    // Values are bound, not spliced into the SQL.
+    let req = test::TestRequest::patch()
+        .uri(&uri)
+        .set_json(serde_json::json!({ "asset_name": "x', asset_brand = 'ibm" }))
+        .to_request();
+    let updated: Asset = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(updated.asset_name, "x', asset_brand = 'ibm");
+    assert_eq!(updated.asset_brand, None);
+
+    let req = test::TestRequest::patch()
+        .uri(&format!("/assets/{}", uuid::Uuid::nil()))
+        .set_json(serde_json::json!({ "asset_name": "phone" }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 404);
+
+    common::drop_schema(&pool, schema).await;
+}
+
+#[actix_web::test]
+#[ignore = "needs DATABASE_URL"]
+async fn post_returns_server_generated_values() {
+    let schema = "handlers_post_test";
+    let pool = common::test_pool(schema).await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .configure(configure),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/categories")
+        .set_json(serde_json::json!({ "category_name": "tools" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 201);
+    assert_eq!(resp.headers().get("location").unwrap(), "/categories/1");
+    let category: Category = test::read_body_json(resp).await;
+    assert_eq!(category.category_id, 1);
+
+    let before = chrono::Utc::now();
+    let req = test::TestRequest::post()
+        .uri("/assets")
+        .set_json(serde_json::json!({ "asset_name": "laptop" }))
+        .to_request();
+    let asset: Asset = test::call_and_read_body_json(&app, req).await;
+    assert!(!asset.asset_id.is_nil());
+    assert!(asset.created_at >= before - chrono::Duration::seconds(5));
+    assert_eq!(asset.asset_brand, None);
+
+    // A defaulted column is only defaulted when the body leaves it out.
+    let req = test::TestRequest::post()
+        .uri("/assets")
+        .set_json(serde_json::json!({ "asset_id": uuid::Uuid::max(), "asset_name": "phone" }))
+        .to_request();
+    let asset: Asset = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(asset.asset_id, uuid::Uuid::max());
+
+    // Wrong JSON types are rejected before anything reaches the database.
+    for body in [
+        serde_json::json!({ "asset_name": 5 }),
+        serde_json::json!({ "asset_name": ["laptop"] }),
+        serde_json::json!({ "asset_name": null }),
+    ] {
+        let req = test::TestRequest::post()
+            .uri("/assets")
+            .set_json(&body)
+            .to_request();
+        assert_eq!(
+            test::call_service(&app, req).await.status(),
+            400,
+            "{}",
+            body
+        );
+    }
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM assets")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+
+    common::drop_schema(&pool, schema).await;
+}
+
+#[actix_web::test]
+#[ignore = "needs DATABASE_URL"]
+async fn put_and_on_conflict_upsert() {
+    let schema = "handlers_upsert_test";
+    let pool = common::test_pool(schema).await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .configure(configure),
+    )
+    .await;
+
+    let uri = format!("/assets/{}", uuid::Uuid::nil());
+    let req = test::TestRequest::put()
+        .uri(&uri)
+        .set_json(serde_json::json!({ "asset_name": "laptop" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 201);
+    assert_eq!(resp.headers().get("location").unwrap(), uri.as_str());
+    let created: Asset = test::read_body_json(resp).await;
+    assert!(created.asset_id.is_nil());
+
+    // The same request again replaces the row, keeping server-set columns.
+    let req = test::TestRequest::put()
+        .uri(&uri)
+        .set_json(serde_json::json!({ "asset_name": "desktop" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let updated: Asset = test::read_body_json(resp).await;
+    assert_eq!(updated.asset_name, "desktop");
+    assert_eq!(updated.created_at, created.created_at);
+
+    for (on_conflict, status) in [
+        ("assets_asset_name_key", 200),
+        ("asset_name", 200),
+        ("asset_brand", 400),
+    ] {
+        let req = test::TestRequest::post()
+            .uri(&format!("/assets?on_conflict={}", on_conflict))
+            .set_json(serde_json::json!({ "asset_name": "desktop" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), status, "{}", on_conflict);
+    }
+    let req = test::TestRequest::post()
+        .uri("/assets?on_conflict=asset_name")
+        .set_json(serde_json::json!({ "asset_name": "phone" }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 201);
+    // A defaulted key may be supplied, and so conflicted on.
+    for (name, status) in [("tools", 201), ("hardware", 200)] {
+        let req = test::TestRequest::post()
+            .uri("/categories?on_conflict=category_id")
+            .set_json(serde_json::json!({ "category_id": 7, "category_name": name }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), status, "{}", name);
+    }
+    let name: String =
+        sqlx::query_scalar("SELECT category_name FROM categories WHERE category_id = 7")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(name, "hardware");
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM assets")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+
+    common::drop_schema(&pool, schema).await;
+}
+
+#[actix_web::test]
+#[ignore = "needs DATABASE_URL"]
+async fn unique_keys_get_lookup_routes() {
+    let schema = "handlers_lookup_test";
+    let pool = common::test_pool(schema).await;
+    let collection = uuid::Uuid::max();
+    sqlx::raw_sql(&format!(
+        "INSERT INTO assets (asset_id, asset_name) VALUES ('{nil}', 'laptop');
+         INSERT INTO collections VALUES ('{collection}', 'office');
+         INSERT INTO asset_collection VALUES ('{nil}', '{collection}', 3);",
+        nil = uuid::Uuid::nil()
+    ))
+    .execute(&pool)
+    .await
+    .unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .configure(configure),
+    )
+    .await;
+
+    let req = test::TestRequest::get()
+        .uri("/assets/by_asset_name/laptop")
+        .to_request();
+    let asset: Asset = test::call_and_read_body_json(&app, req).await;
+    assert!(asset.asset_id.is_nil());
+    let req = test::TestRequest::get()
+        .uri("/assets/by_asset_name/phone")
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 404);
+
+    // Composite keys take one segment per column.
+    let req = test::TestRequest::get()
+        .uri(&format!(
+            "/asset_collection/by_collection_id_and_position/{}/3",
+            collection
+        ))
+        .to_request();
+    let link: AssetCollection = test::call_and_read_body_json(&app, req).await;
+    assert!(link.asset_id.is_nil());
+    let found =
+        AssetCollectionRepository::find_by_collection_id_and_position(&pool, &collection, &4)
+            .await
+            .unwrap();
+    assert!(found.is_none());
+
+    common::drop_schema(&pool, schema).await;
+}
+
+#[actix_web::test]
+#[ignore = "needs DATABASE_URL"]
+async fn etags_make_updates_conditional() {
+    let schema = "handlers_etag_test";
+    let pool = common::test_pool(schema).await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .configure(configure),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/collections")
+        .set_json(serde_json::json!({ "collection_name": "office" }))
+        .to_request();
+    let created: Collection = test::call_and_read_body_json(&app, req).await;
+    let uri = format!("/collections/{}", created.collection_id);
+
+    // The `version` column is the tag.
+    let resp = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+    assert_eq!(resp.headers().get("etag").unwrap(), r#""1""#);
+    let req = test::TestRequest::get()
+        .uri(&uri)
+        .insert_header(("If-None-Match", r#"W/"1""#))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 304);
+
+    let req = test::TestRequest::patch()
+        .uri(&uri)
+        .insert_header(("If-Match", r#""1""#))
+        .set_json(serde_json::json!({ "collection_name": "home" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers().get("etag").unwrap(), r#""2""#);
+
+    // A second editor still holding version 1 loses.
+    let req = test::TestRequest::patch()
+        .uri(&uri)
+        .insert_header(("If-Match", r#""1""#))
+        .set_json(serde_json::json!({ "collection_name": "garage" }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 412);
+    let req = test::TestRequest::delete()
+        .uri(&uri)
+        .insert_header(("If-Match", r#""1""#))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 412);
+    let req = test::TestRequest::get()
+        .uri(&uri)
+        .insert_header(("If-None-Match", r#""1""#))
+        .to_request();
+    let current: Collection = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(current.collection_name, "home");
+
+    // Clients cannot set the version; every update increments it.
+    let req = test::TestRequest::patch()
+        .uri(&uri)
+        .insert_header(("If-Match", r#""2""#))
+        .set_json(serde_json::json!({ "collection_name": "garage", "version": 10 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers().get("etag").unwrap(), r#""3""#);
+
+    // Tables without a version or updated_at column hash the row.
+    let req = test::TestRequest::post()
+        .uri("/assets")
+        .set_json(serde_json::json!({ "asset_name": "laptop" }))
+        .to_request();
+    let asset: Asset = test::call_and_read_body_json(&app, req).await;
+    let req = test::TestRequest::delete()
+        .uri(&format!("/assets/{}", asset.asset_id))
+        .insert_header(("If-Match", asset.etag()))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 204);
+
+    let req = test::TestRequest::delete()
+        .uri(&uri)
+        .insert_header(("If-Match", "*"))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 204);
+
+    common::drop_schema(&pool, schema).await;
+}
+
+#[actix_web::test]
+#[ignore = "needs DATABASE_URL"]
+async fn audit_columns_are_set_server_side() {
+    use actix_web::dev::Service;
+    use actix_web::HttpMessage;
+
+    let schema = "handlers_audit_test";
+    let pool = common::test_pool(schema).await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .wrap_fn(|req, srv| {
+                let user = req.headers().get("x-user").and_then(|v| v.to_str().ok());
+                if let Some(user) = user.map(String::from) {
+                    req.extensions_mut().insert(audit::Actor(user));
+                }
+                srv.call(req)
+            })
+            .configure(configure),
+    )
+    .await;
+
+    // Audit fields in the body are ignored.
+    let req = test::TestRequest::post()
+        .uri("/collections")
+        .insert_header(("x-user", "alice"))
+        .set_json(serde_json::json!({
+            "collection_name": "office",
+            "created_by": "mallory",
+            "updated_at": "2000-01-01T00:00:00Z",
+        }))
+        .to_request();
+    let created: Collection = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(created.created_by.as_deref(), Some("alice"));
+    assert_eq!(created.updated_by.as_deref(), Some("alice"));
+    let inserted_at = created.updated_at.unwrap();
+    assert!(inserted_at > chrono::Utc::now() - chrono::Duration::minutes(1));
+
+    let uri = format!("/collections/{}", created.collection_id);
+    let req = test::TestRequest::patch()
+        .uri(&uri)
+        .insert_header(("x-user", "bob"))
+        .set_json(serde_json::json!({ "collection_name": "home" }))
+        .to_request();
+    let updated: Collection = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(updated.created_by.as_deref(), Some("alice"));
+    assert_eq!(updated.updated_by.as_deref(), Some("bob"));
+    assert!(updated.updated_at.unwrap() >= inserted_at);
+
+    // Without an actor the `*_by` columns are NULL.
+    let req = test::TestRequest::patch()
+        .uri(&uri)
+        .set_json(serde_json::json!({ "collection_name": "garage" }))
+        .to_request();
+    let anonymous: Collection = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(anonymous.created_by.as_deref(), Some("alice"));
+    assert_eq!(anonymous.updated_by, None);
+
+    common::drop_schema(&pool, schema).await;
+}
+
+#[actix_web::test]
+#[ignore = "needs DATABASE_URL"]
+async fn soft_deletes_record_the_actor() {
+    use actix_web::dev::Service;
+    use actix_web::HttpMessage;
+
+    let schema = "handlers_soft_delete_actor_test";
+    let pool = common::test_pool(schema).await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .wrap_fn(|req, srv| {
+                let user = req.headers().get("x-user").and_then(|v| v.to_str().ok());
+                if let Some(user) = user.map(String::from) {
+                    req.extensions_mut().insert(audit::Actor(user));
+                }
+                srv.call(req)
+            })
+            .configure(configure),
+    )
+    .await;
+    let asset = uuid::Uuid::nil();
+    let collections = [
+        uuid::Uuid::from_u128(1),
+        uuid::Uuid::from_u128(2),
+        uuid::Uuid::from_u128(3),
+    ];
+    sqlx::raw_sql(&format!(
+        "INSERT INTO assets (asset_id, asset_name) VALUES ('{asset}', 'laptop');
+         INSERT INTO collections (collection_id, collection_name) VALUES
+             ('{0}', 'office'), ('{1}', 'home'), ('{2}', 'garage');
+         INSERT INTO asset_collection (asset_id, collection_id, position) VALUES
+             ('{asset}', '{0}', 1), ('{asset}', '{1}', 2), ('{asset}', '{2}', 3);",
+        collections[0], collections[1], collections[2]
+    ))
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    // By key, unlinked through the nested route, and by filter.
+    for (uri, user) in [
+        (
+            format!("/asset_collection/{}/{}", asset, collections[0]),
+            "alice",
+        ),
+        (
+            format!("/collections/{}/assets/{}", collections[1], asset),
+            "bob",
+        ),
+        (String::from("/asset_collection?position=3"), "carol"),
+    ] {
+        let req = test::TestRequest::delete()
+            .uri(&uri)
+            .insert_header(("x-user", user))
+            .to_request();
+        assert!(test::call_service(&app, req).await.status().is_success());
+    }
+    let updated_by: Vec<Option<String>> = sqlx::query_scalar(
+        "SELECT updated_by FROM asset_collection WHERE deleted_at IS NOT NULL ORDER BY position",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        updated_by,
+        [
+            Some("alice".into()),
+            Some("bob".into()),
+            Some("carol".into())
+        ]
+    );
+
+    common::drop_schema(&pool, schema).await;
+}
+
+#[actix_web::test]
+#[ignore = "needs DATABASE_URL"]
+async fn soft_deleted_rows_are_hidden_and_restorable() {
+    let schema = "handlers_soft_delete_test";
+    let pool = common::test_pool(schema).await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .configure(configure),
+    )
+    .await;
+    let asset = uuid::Uuid::nil();
+    let collection = uuid::Uuid::max();
+    sqlx::raw_sql(&format!(
+        "INSERT INTO assets (asset_id, asset_name) VALUES ('{asset}', 'laptop');
+         INSERT INTO collections (collection_id, collection_name) VALUES ('{collection}', 'office');
+         INSERT INTO asset_collection (asset_id, collection_id) VALUES ('{asset}', '{collection}');"
+    ))
+    .execute(&pool)
+    .await
+    .unwrap();
+    let uri = format!("/asset_collection/{}/{}", asset, collection);
+
+    // asset_collection has a deleted_at column, so DELETE only marks the row.
+    let req = test::TestRequest::delete().uri(&uri).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 204);
+    let req = test::TestRequest::delete().uri(&uri).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 404);
+    let kept: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM asset_collection")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(kept, 1);
+
+    let req = test::TestRequest::get().uri(&uri).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 404);
+    let req = test::TestRequest::get()
+        .uri(&format!("{}?include_deleted=true", uri))
+        .to_request();
+    let deleted: AssetCollection = test::call_and_read_body_json(&app, req).await;
+    assert!(deleted.deleted_at.is_some());
+    let req = test::TestRequest::patch()
+        .uri(&uri)
+        .set_json(serde_json::json!({ "position": 1 }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 404);
+
+    let req = test::TestRequest::get()
+        .uri("/asset_collection")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.headers().get("X-Total-Count").unwrap(), "0");
+    let req = test::TestRequest::get()
+        .uri("/asset_collection?include_deleted=true")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.headers().get("X-Total-Count").unwrap(), "1");
+    let req = test::TestRequest::get()
+        .uri("/asset_collection?include_deleted=yes")
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 400);
+    let req = test::TestRequest::get()
+        .uri(&format!("/collections/{}/assets", collection))
+        .to_request();
+    let linked: Vec<Asset> = test::call_and_read_body_json(&app, req).await;
+    assert!(linked.is_empty());
+
+    let req = test::TestRequest::post()
+        .uri(&format!("{}/restore", uri))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    assert!(resp.headers().contains_key("etag"));
+    let restored: AssetCollection = test::read_body_json(resp).await;
+    assert_eq!(restored.deleted_at, None);
+    let req = test::TestRequest::get().uri(&uri).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 200);
+
+    // Linking again brings back an unlinked row.
+    let link = format!("/collections/{}/assets/{}", collection, asset);
+    let req = test::TestRequest::delete().uri(&link).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 204);
+    let req = test::TestRequest::put().uri(&link).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 204);
+    let req = test::TestRequest::get().uri(&uri).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 200);
+
+    // Tables without the column delete for real and have no `include_deleted`.
+    let req = test::TestRequest::get()
+        .uri("/assets?include_deleted=true")
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 400);
+    let req = test::TestRequest::post()
+        .uri(&format!("/assets/{}/restore", asset))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 404);
+
+    common::drop_schema(&pool, schema).await;
+}
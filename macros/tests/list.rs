@@ -0,0 +1,231 @@
+mod common;
+
+use actix_web::test::{call_and_read_body_json, call_service, init_service, TestRequest};
+use actix_web::{delete, get, patch, post, web, App, HttpResponse};
+use macros::generate_structs_from_ddl;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgPool;
+
+generate_structs_from_ddl!("tests/models.sql");
+
+const SCHEMA: &str = "list_test";
+
+#[test]
+fn filters_are_restricted_to_known_columns_and_operators() {
+    let list = list_query::ListQuery::parse(
+        "asset_brand=ibm&asset_name[ilike]=%25top&created_at[gte]=2024-01-01&asset_id[in]=a,b",
+        &AssetRepository::TABLE,
+    )
+    .unwrap();
+    assert_eq!(list.filters.len(), 4);
+    assert_eq!(list.filters[3].values, ["a", "b"]);
+
+    for query in [
+        "nope=1",
+        "asset_name[between]=a",
+        "created_at[like]=2024%25",
+        "asset_brand[is]=maybe",
+        "asset_name;drop table assets=1",
+    ] {
+        let err = list_query::ListQuery::parse(query, &AssetRepository::TABLE).unwrap_err();
+        assert_eq!(err.status, 400, "{}", query);
+    }
+}
+
+#[test]
+fn paging_parameters_are_validated() {
+    let list = list_query::ListQuery::parse("", &AssetRepository::TABLE).unwrap();
+    assert_eq!((list.limit, list.offset), (list_query::PAGE_SIZE, 0));
+    assert_eq!(list.order[0].column.name, "asset_id");
+    let list =
+        list_query::ListQuery::parse("limit=100000&offset=3", &AssetRepository::TABLE).unwrap();
+    assert_eq!((list.limit, list.offset), (list_query::MAX_PAGE_SIZE, 3));
+
+    for query in [
+        "limit=0",
+        "limit=ten",
+        "offset=-1",
+        "cursor=zz",
+        "cursor=5b5d",
+        "cursor=5b2261225d&offset=1",
+    ] {
+        let err = list_query::ListQuery::parse(query, &AssetRepository::TABLE).unwrap_err();
+        assert_eq!(err.status, 400, "{}", query);
+    }
+}
+
+#[test]
+fn sort_and_fields_are_restricted_to_known_columns() {
+    let list = list_query::ListQuery::parse(
+        "sort=-created_at,asset_name&fields=asset_name",
+        &AssetRepository::TABLE,
+    )
+    .unwrap();
+    let order: Vec<(&str, bool)> = list
+        .order
+        .iter()
+        .map(|s| (s.column.name, s.descending))
+        .collect();
+    assert_eq!(
+        order,
+        [
+            ("created_at", true),
+            ("asset_name", false),
+            ("asset_id", false)
+        ]
+    );
+    let selected: Vec<&str> = list.selected().iter().map(|c| c.name).collect();
+    assert_eq!(selected, ["asset_name", "created_at", "asset_id"]);
+
+    for query in ["sort=nope", "sort=-", "fields=asset_id,nope", "fields="] {
+        let err = list_query::ListQuery::parse(query, &AssetRepository::TABLE).unwrap_err();
+        assert_eq!(err.status, 400, "{}", query);
+        assert!(
+            err.detail.unwrap().contains("asset_id, asset_name"),
+            "{}",
+            query
+        );
+    }
+    // Cursors need a NOT NULL order.
+    let err = list_query::ListQuery::parse(
+        "sort=asset_brand&cursor=5b2261222c2262225d",
+        &AssetRepository::TABLE,
+    )
+    .unwrap_err();
+    assert_eq!(err.status, 400);
+}
+
+#[actix_web::test]
+#[ignore = "needs DATABASE_URL"]
+async fn list_endpoint_filters_rows() {
+    let pool = common::test_pool(SCHEMA).await;
+    sqlx::raw_sql(
+        "INSERT INTO brands VALUES ('ibm'), ('hp');
+         INSERT INTO assets (asset_name, asset_brand, created_at) VALUES
+             ('laptop', 'ibm', '2024-01-01'),
+             ('Desktop', 'hp', '2024-06-01'),
+             ('tablet', NULL, '2025-01-01');",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let app = init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .configure(configure),
+    )
+    .await;
+
+    for (query, expected) in [
+        ("", vec!["laptop", "Desktop", "tablet"]),
+        ("asset_brand=ibm", vec!["laptop"]),
+        ("asset_brand[ne]=ibm", vec!["Desktop"]),
+        ("asset_brand[in]=ibm,hp", vec!["laptop", "Desktop"]),
+        ("asset_brand[is]=null", vec!["tablet"]),
+        ("asset_brand[is]=not_null", vec!["laptop", "Desktop"]),
+        ("created_at[gte]=2024-06-01", vec!["Desktop", "tablet"]),
+        (
+            "created_at[gt]=2024-01-01&created_at[lt]=2025-01-01",
+            vec!["Desktop"],
+        ),
+        ("asset_name[like]=%25top", vec!["laptop", "Desktop"]),
+        ("asset_name[ilike]=d%25", vec!["Desktop"]),
+    ] {
+        let req = TestRequest::get()
+            .uri(&format!("/assets?{}&sort=created_at", query))
+            .to_request();
+        let rows: Vec<Asset> = call_and_read_body_json(&app, req).await;
+        let names: Vec<&str> = rows.iter().map(|a| a.asset_name.as_str()).collect();
+        assert_eq!(names, expected, "{}", query);
+    }
+
+    // The value is bound and cast, so a malformed one is rejected by Postgres.
+    let req = TestRequest::get()
+        .uri("/assets?created_at=not-a-date")
+        .to_request();
+    assert_eq!(call_service(&app, req).await.status(), 400);
+    let req = TestRequest::get()
+        .uri("/assets?asset_name='%20OR%201=1")
+        .to_request();
+    let rows: Vec<Asset> = call_and_read_body_json(&app, req).await;
+    assert!(rows.is_empty());
+
+    common::drop_schema(&pool, SCHEMA).await;
+}
+
+#[actix_web::test]
+#[ignore = "needs DATABASE_URL"]
+async fn list_endpoint_pages_by_cursor_and_offset() {
+    let schema = "list_paging_test";
+    let pool = common::test_pool(schema).await;
+    sqlx::raw_sql(
+        "INSERT INTO categories (category_name)
+         SELECT 'c' || i FROM generate_series(1, 5) AS i;",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let app = init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .configure(configure),
+    )
+    .await;
+
+    // Follow the Link headers until there is no next page.
+    let mut uri = String::from("/categories?category_name[ne]=c3&limit=2");
+    let mut seen = Vec::new();
+    loop {
+        let res = call_service(&app, TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(res.headers().get("X-Total-Count").unwrap(), "4");
+        let link = res
+            .headers()
+            .get("Link")
+            .map(|l| l.to_str().unwrap().to_string());
+        let rows: Vec<Category> = actix_web::test::read_body_json(res).await;
+        seen.extend(rows.into_iter().map(|c| c.category_name));
+        match link {
+            Some(link) => {
+                assert!(link.contains("cursor=") && link.ends_with(r#">; rel="next""#));
+                uri = link[1..link.find('>').unwrap()].to_string();
+            }
+            None => break,
+        }
+    }
+    assert_eq!(seen, ["c1", "c2", "c4", "c5"]);
+
+    let req = TestRequest::get()
+        .uri("/categories?limit=2&offset=2")
+        .to_request();
+    let res = call_service(&app, req).await;
+    assert_eq!(
+        res.headers().get("Link").unwrap(),
+        r#"</categories?limit=2&offset=4>; rel="next""#
+    );
+    let rows: Vec<Category> = actix_web::test::read_body_json(res).await;
+    let names: Vec<&str> = rows.iter().map(|c| c.category_name.as_str()).collect();
+    assert_eq!(names, ["c3", "c4"]);
+
+    // Descending sort with sparse fields, still paged by cursor.
+    let mut uri = String::from("/categories?sort=-category_name&fields=category_name&limit=3");
+    let mut seen = Vec::new();
+    loop {
+        let res = call_service(&app, TestRequest::get().uri(&uri).to_request()).await;
+        let link = res
+            .headers()
+            .get("Link")
+            .map(|l| l.to_str().unwrap().to_string());
+        let rows: Vec<serde_json::Value> = actix_web::test::read_body_json(res).await;
+        for row in rows {
+            assert_eq!(row.as_object().unwrap().len(), 1);
+            seen.push(row["category_name"].as_str().unwrap().to_string());
+        }
+        match link {
+            Some(link) => uri = link[1..link.find('>').unwrap()].to_string(),
+            None => break,
+        }
+    }
+    assert_eq!(seen, ["c5", "c4", "c3", "c2", "c1"]);
+
+    common::drop_schema(&pool, schema).await;
+}
@@ -0,0 +1,103 @@
+use actix_web::{delete, get, patch, post, web, HttpResponse};
+use macros::generate_structs_from_ddl;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgPool;
+
+generate_structs_from_ddl!("tests/models.sql");
+
+#[test]
+fn create_model_makes_defaulted_columns_optional() {
+    let new: NewAsset = serde_json::from_value(serde_json::json!({
+        "asset_name": "laptop",
+    }))
+    .unwrap();
+    assert_eq!(new.asset_name, "laptop");
+    assert_eq!(new.asset_brand, None);
+    assert_eq!(new.asset_id, None);
+    assert_eq!(
+        serde_json::to_value(&new).unwrap(),
+        serde_json::json!({ "asset_name": "laptop" })
+    );
+
+    // NOT NULL columns without a default are required.
+    let missing = serde_json::from_value::<NewAsset>(serde_json::json!({ "asset_brand": "ibm" }));
+    assert!(missing.is_err());
+
+    let category: NewCategory =
+        serde_json::from_value(serde_json::json!({ "category_name": "tools" })).unwrap();
+    assert_eq!(category.category_name, "tools");
+    assert_eq!(category.category_id, None);
+
+    // A defaulted column can still be set.
+    let category: NewCategory = serde_json::from_value(serde_json::json!({
+        "category_id": 7,
+        "category_name": "tools",
+    }))
+    .unwrap();
+    assert_eq!(category.category_id, Some(7));
+}
+
+#[test]
+fn patch_model_is_optional_without_primary_key() {
+    let patch: AssetPatch =
+        serde_json::from_value(serde_json::json!({ "asset_brand": "ibm" })).unwrap();
+    assert_eq!(patch.asset_brand, Some(Some(String::from("ibm"))));
+    assert_eq!(patch.asset_name, None);
+    assert_eq!(patch.present_fields(), ["asset_brand"]);
+
+    // An explicit null clears a nullable column, an absent field is left alone.
+    let patch: AssetPatch =
+        serde_json::from_value(serde_json::json!({ "asset_brand": null })).unwrap();
+    assert_eq!(patch.asset_brand, Some(None));
+    assert_eq!(
+        serde_json::to_value(AssetPatch::default()).unwrap(),
+        serde_json::json!({})
+    );
+}
+
+#[test]
+fn read_model_follows_nullability() {
+    let row = Asset {
+        asset_id: uuid::Uuid::nil(),
+        asset_name: String::from("laptop"),
+        asset_brand: None,
+        created_at: chrono::DateTime::<chrono::Utc>::default(),
+    };
+    let category = Category {
+        category_id: 1,
+        category_name: String::from("tools"),
+    };
+    assert_eq!(row.asset_name, "laptop");
+    assert_eq!(category.category_id, 1);
+}
+
+#[test]
+fn hashed_etags_are_stable() {
+    // FNV-1a of `{"brand_id":"ibm"}`, the same in every build.
+    let brand = Brand {
+        brand_id: String::from("ibm"),
+    };
+    assert_eq!(brand.etag(), r#""3b816c524d688eaa""#);
+}
+
+#[test]
+fn composite_keys_get_a_key_struct() {
+    let key: AssetCollectionKey = serde_json::from_value(serde_json::json!({
+        "asset_id": uuid::Uuid::nil(),
+        "collection_id": uuid::Uuid::max(),
+    }))
+    .unwrap();
+    assert_eq!(key.collection_id, uuid::Uuid::max());
+
+    // Key columns are never patched.
+    let patch = AssetCollectionPatch {
+        position: Some(Some(2)),
+    };
+    assert_eq!(
+        serde_json::to_value(&patch).unwrap(),
+        serde_json::json!({ "position": 2 })
+    );
+    let _ = AssetKey {
+        asset_id: uuid::Uuid::nil(),
+    };
+}
@@ -0,0 +1,38 @@
+CREATE TABLE categories (
+	category_id serial NOT NULL,
+	category_name text NOT NULL,
+	CONSTRAINT categories_pkey PRIMARY KEY (category_id)
+);
+CREATE TABLE brands (
+	brand_id text NOT NULL,
+	CONSTRAINT brands_pkey PRIMARY KEY (brand_id)
+);
+CREATE TABLE assets (
+	asset_id uuid DEFAULT gen_random_uuid() NOT NULL,
+	asset_name text NOT NULL,
+	asset_brand text NULL,
+	created_at timestamptz DEFAULT now() NOT NULL,
+	CONSTRAINT assets_pkey PRIMARY KEY (asset_id),
+	CONSTRAINT assets_asset_name_key UNIQUE (asset_name),
+	CONSTRAINT assets_brand_fk FOREIGN KEY (asset_brand) REFERENCES brands(brand_id)
+);
+CREATE TABLE collections (
+	collection_id uuid DEFAULT gen_random_uuid() NOT NULL,
+	collection_name text NOT NULL,
+	"version" int4 DEFAULT 1 NOT NULL,
+	updated_at timestamptz NULL,
+	created_by text NULL,
+	updated_by text NULL,
+	CONSTRAINT collections_pkey PRIMARY KEY (collection_id)
+);
+CREATE TABLE asset_collection (
+	asset_id uuid NOT NULL,
+	collection_id uuid NOT NULL,
+	"position" int4 NULL,
+	deleted_at timestamptz NULL,
+	updated_by text NULL,
+	CONSTRAINT asset_collection_pkey PRIMARY KEY (asset_id, collection_id),
+	CONSTRAINT asset_collection_position_key UNIQUE (collection_id, "position"),
+	CONSTRAINT asset_collection_asset_fk FOREIGN KEY (asset_id) REFERENCES assets(asset_id) ON DELETE CASCADE,
+	CONSTRAINT asset_collection_collection_fk FOREIGN KEY (collection_id) REFERENCES collections(collection_id) ON DELETE CASCADE
+);
@@ -0,0 +1,113 @@
+mod common;
+
+use actix_web::test::{call_and_read_body_json, call_service, init_service, TestRequest};
+use actix_web::{delete, get, patch, post, web, App, HttpResponse};
+use macros::generate_structs_from_ddl;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgPool;
+
+generate_structs_from_ddl!("tests/models.sql");
+
+const SCHEMA: &str = "nested_test";
+
+#[actix_web::test]
+#[ignore = "needs DATABASE_URL"]
+async fn foreign_keys_give_nested_and_join_routes() {
+    let pool = common::test_pool(SCHEMA).await;
+    sqlx::raw_sql("INSERT INTO brands VALUES ('ibm'), ('hp')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let app = init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .configure(configure),
+    )
+    .await;
+
+    // The foreign key column comes from the path, even if the body disagrees.
+    let req = TestRequest::post()
+        .uri("/brands/ibm/assets")
+        .set_json(serde_json::json!({ "asset_name": "laptop", "asset_brand": "hp" }))
+        .to_request();
+    let resp = call_service(&app, req).await;
+    assert_eq!(resp.status(), 201);
+    let location = resp.headers().get("location").unwrap().to_str().unwrap();
+    assert!(location.starts_with("/assets/"), "{}", location);
+    let laptop: Asset = actix_web::test::read_body_json(resp).await;
+    assert_eq!(laptop.asset_brand.as_deref(), Some("ibm"));
+
+    let req = TestRequest::post()
+        .uri("/brands/hp/assets")
+        .set_json(serde_json::json!({ "asset_name": "desktop" }))
+        .to_request();
+    let desktop: Asset = call_and_read_body_json(&app, req).await;
+
+    let req = TestRequest::get()
+        .uri("/brands/ibm/assets?fields=asset_name")
+        .to_request();
+    let resp = call_service(&app, req).await;
+    assert_eq!(resp.headers().get("X-Total-Count").unwrap(), "1");
+    let rows: serde_json::Value = actix_web::test::read_body_json(resp).await;
+    assert_eq!(rows, serde_json::json!([{ "asset_name": "laptop" }]));
+
+    // Link both assets to a collection, once each.
+    let req = TestRequest::post()
+        .uri("/collections")
+        .set_json(serde_json::json!({ "collection_name": "office" }))
+        .to_request();
+    let office: Collection = call_and_read_body_json(&app, req).await;
+    for asset in [&laptop, &desktop, &laptop] {
+        let req = TestRequest::put()
+            .uri(&format!(
+                "/collections/{}/assets/{}",
+                office.collection_id, asset.asset_id
+            ))
+            .to_request();
+        assert_eq!(call_service(&app, req).await.status(), 204);
+    }
+
+    let req = TestRequest::get()
+        .uri(&format!(
+            "/collections/{}/assets?sort=asset_name",
+            office.collection_id
+        ))
+        .to_request();
+    let rows: Vec<Asset> = call_and_read_body_json(&app, req).await;
+    let names: Vec<&str> = rows.iter().map(|a| a.asset_name.as_str()).collect();
+    assert_eq!(names, ["desktop", "laptop"]);
+
+    let req = TestRequest::get()
+        .uri(&format!("/assets/{}/collections", laptop.asset_id))
+        .to_request();
+    let rows: Vec<Collection> = call_and_read_body_json(&app, req).await;
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].collection_name, "office");
+
+    let unlink = format!(
+        "/assets/{}/collections/{}",
+        laptop.asset_id, office.collection_id
+    );
+    let req = TestRequest::delete().uri(&unlink).to_request();
+    assert_eq!(call_service(&app, req).await.status(), 204);
+    let req = TestRequest::delete().uri(&unlink).to_request();
+    assert_eq!(call_service(&app, req).await.status(), 404);
+
+    let req = TestRequest::get()
+        .uri(&format!("/collections/{}/assets", office.collection_id))
+        .to_request();
+    let rows: Vec<Asset> = call_and_read_body_json(&app, req).await;
+    assert_eq!(rows.len(), 1);
+
+    // Linking to a missing row is a foreign key violation.
+    let req = TestRequest::put()
+        .uri(&format!(
+            "/collections/{}/assets/{}",
+            uuid::Uuid::nil(),
+            laptop.asset_id
+        ))
+        .to_request();
+    assert_eq!(call_service(&app, req).await.status(), 422);
+
+    common::drop_schema(&pool, SCHEMA).await;
+}
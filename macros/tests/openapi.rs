@@ -0,0 +1,144 @@
+use actix_web::test::{
+    call_and_read_body, call_and_read_body_json, call_service, init_service, TestRequest,
+};
+use actix_web::{delete, get, patch, post, web, App, HttpResponse};
+use macros::generate_structs_from_ddl;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgPool;
+
+generate_structs_from_ddl!("tests/models.sql");
+
+#[actix_web::test]
+async fn openapi_document_describes_the_generated_routes() {
+    let app = init_service(App::new().configure(configure)).await;
+    let req = TestRequest::get().uri("/openapi.json").to_request();
+    let document: serde_json::Value = call_and_read_body_json(&app, req).await;
+    assert_eq!(document["openapi"], "3.1.0");
+    assert_eq!(document["info"]["title"], "macros");
+    assert!(document.get("servers").is_none());
+    assert_eq!(
+        document,
+        serde_json::from_str::<serde_json::Value>(OPENAPI).unwrap()
+    );
+
+    let paths = &document["paths"];
+    let list = &paths["/assets"]["get"];
+    assert_eq!(list["operationId"], "get_assets");
+    let parameters: Vec<&str> = list["parameters"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["name"].as_str().or(p["$ref"].as_str()).unwrap())
+        .collect();
+    for name in ["asset_name", "sort", "fields", "expand"] {
+        assert!(parameters.contains(&name), "{} in {:?}", name, parameters);
+    }
+    assert!(parameters.contains(&"#/components/parameters/cursor"));
+    assert_eq!(
+        list["responses"]["200"]["headers"]["X-Total-Count"]["$ref"],
+        "#/components/headers/X-Total-Count"
+    );
+    assert_eq!(
+        paths["/assets"]["post"]["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+        "#/components/schemas/NewAsset"
+    );
+    assert_eq!(
+        paths["/assets"]["post"]["responses"]["409"]["$ref"],
+        "#/components/responses/Conflict"
+    );
+    let member = &paths["/assets/{asset_id}"];
+    for method in ["get", "patch", "put", "delete"] {
+        assert!(
+            member[method].is_object(),
+            "{} /assets/{{asset_id}}",
+            method
+        );
+    }
+    assert_eq!(member["get"]["parameters"][0]["schema"]["format"], "uuid");
+    assert!(member["get"]["responses"]["304"].is_object());
+    assert!(member["patch"]["responses"]["412"].is_object());
+    assert!(paths["/assets/by_asset_name/{asset_name}"]["get"].is_object());
+    assert!(paths["/assets/bulk"]["post"].is_object());
+    assert!(paths["/brands/{brand_id}/assets"]["get"].is_object());
+    assert!(paths["/asset_collection/{asset_id}/{collection_id}/restore"]["post"].is_object());
+    assert!(paths["/collections/{collection_id}/assets/{asset_id}"]["put"].is_object());
+    assert!(paths["/batch"]["post"].is_object());
+
+    // Operation ids follow the handler names, so they are unique.
+    let mut ids: Vec<&str> = paths
+        .as_object()
+        .unwrap()
+        .values()
+        .flat_map(|item| item.as_object().unwrap().values())
+        .map(|operation| operation["operationId"].as_str().unwrap())
+        .collect();
+    let count = ids.len();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), count);
+
+    let schemas = &document["components"]["schemas"];
+    let asset = &schemas["Asset"];
+    assert_eq!(asset["properties"]["asset_id"]["format"], "uuid");
+    assert!(asset["required"]
+        .as_array()
+        .unwrap()
+        .contains(&serde_json::json!("asset_id")));
+    // Defaulted columns may be sent, but are not required.
+    assert_eq!(
+        schemas["NewAsset"]["properties"]["asset_id"]["format"],
+        "uuid"
+    );
+    assert_eq!(
+        schemas["NewAsset"]["required"],
+        serde_json::json!(["asset_name"])
+    );
+    assert!(schemas["AssetPatch"]["required"].is_null());
+    assert_eq!(
+        schemas["AssetKey"]["required"],
+        serde_json::json!(["asset_id"])
+    );
+    assert!(schemas["Problem"].is_object());
+    // Audit and soft delete columns are maintained by the server.
+    assert!(schemas["NewCollection"]["properties"]["created_by"].is_null());
+    assert!(schemas["NewAssetCollection"]["properties"]["deleted_at"].is_null());
+    assert_eq!(
+        schemas["AssetCollection"]["properties"]["deleted_at"]["type"],
+        serde_json::json!(["string", "null"])
+    );
+}
+
+#[actix_web::test]
+async fn openapi_document_points_at_the_mount_path() {
+    let app = init_service(App::new().service(web::scope("/api").configure(configure))).await;
+    let req = TestRequest::get().uri("/api/openapi.json").to_request();
+    let document: serde_json::Value = call_and_read_body_json(&app, req).await;
+    assert_eq!(document["servers"], serde_json::json!([{ "url": "/api" }]));
+}
+
+#[actix_web::test]
+async fn docs_serve_the_bundled_swagger_ui() {
+    let app = init_service(App::new().configure(configure)).await;
+
+    let req = TestRequest::get().uri("/docs").to_request();
+    let page = call_and_read_body(&app, req).await;
+    let page = std::str::from_utf8(&page).unwrap();
+    assert!(page.contains("docs/swagger-ui-bundle.js"));
+    assert!(page.contains("\"openapi.json\""));
+
+    for (file, content_type) in [
+        ("swagger-ui.css", "text/css; charset=utf-8"),
+        ("swagger-ui-bundle.js", "text/javascript; charset=utf-8"),
+    ] {
+        let req = TestRequest::get()
+            .uri(&format!("/docs/{}", file))
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), 200, "{}", file);
+        assert_eq!(resp.headers().get("content-type").unwrap(), content_type);
+    }
+
+    let req = TestRequest::get().uri("/docs/index.html").to_request();
+    let resp = call_service(&app, req).await;
+    assert_eq!(resp.status(), 404);
+}
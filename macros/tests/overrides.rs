@@ -0,0 +1,93 @@
+use actix_web::test::{call_service, init_service, TestRequest};
+use actix_web::{delete, get, patch, post, web, App, HttpResponse};
+use macros::generate_structs_from_ddl;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgPool;
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, sqlx::Type)]
+#[sqlx(transparent)]
+pub struct Email(String);
+
+generate_structs_from_ddl!(
+    "tests/overrides.sql",
+    config = "tests/rust-openapi.toml",
+    types = { "numeric" => f64 },
+    columns = { "contacts.nickname" => Email },
+    etags = { "contacts" => "work_email" },
+    soft_delete = { "contacts" => "archived_at" },
+    models = { "people" => "Person" },
+);
+
+#[test]
+fn overrides_replace_default_types() {
+    // uuid and *_email come from rust-openapi.toml, numeric from the inline
+    // argument, which wins over the file.
+    let row = Contact {
+        contact_id: String::from("c1"),
+        work_email: Email(String::from("a@example.com")),
+        home_email: None,
+        amount: Some(1.5f64),
+        amounts: Some(vec![1.5f64]),
+        nickname: Some(Email(String::from("nick"))),
+        modified_at: None,
+        archived_at: None,
+    };
+    assert_eq!(row.work_email, Email(String::from("a@example.com")));
+    // The inline etags entry replaces the file's `hash`.
+    assert_eq!(row.etag(), r#""a@example.com""#);
+    // `modified_at` is the file's `updated_at` audit column and `archived_at` the
+    // soft delete column, so clients cannot set them.
+    let new = NewContact {
+        contact_id: String::from("c1"),
+        work_email: Email(String::from("a@example.com")),
+        home_email: None,
+        amount: None,
+        amounts: None,
+        nickname: None,
+    };
+    assert_eq!(new.contact_id, row.contact_id);
+}
+
+#[test]
+fn model_names_keep_words_that_only_look_plural() {
+    let status = Status { status_id: 1 };
+    let news = News { news_id: 1 };
+    // The inline name wins over the file's `Human`.
+    let person = Person { person_id: 1 };
+    let _: (NewStatus, NewNews, PersonPatch) = (
+        NewStatus { status_id: 1 },
+        NewNews { news_id: 1 },
+        PersonPatch::default(),
+    );
+    assert_eq!(
+        (status.status_id, news.news_id, person.person_id),
+        (1, 1, 1)
+    );
+}
+
+#[actix_web::test]
+async fn configure_registers_routes_under_prefix() {
+    // No query runs, so the pool never connects.
+    let pool = PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+    let app = init_service(
+        App::new()
+            .app_data(web::Data::new(pool))
+            .configure(configure),
+    )
+    .await;
+
+    let req = TestRequest::post()
+        .uri("/api/v1/contacts")
+        .insert_header(("content-type", "application/json"))
+        .set_payload("{")
+        .to_request();
+    let resp = call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/problem+json"
+    );
+
+    let req = TestRequest::post().uri("/contacts").to_request();
+    assert_eq!(call_service(&app, req).await.status(), 404);
+}
@@ -0,0 +1,26 @@
+CREATE TABLE contacts (
+	contact_id uuid NOT NULL,
+	work_email text NOT NULL,
+	home_email text NULL,
+	amount numeric(10, 2) NULL,
+	amounts numeric[] NULL,
+	nickname text NULL,
+	modified_at timestamptz NULL,
+	archived_at timestamptz NULL,
+	CONSTRAINT contacts_pkey PRIMARY KEY (contact_id)
+);
+
+CREATE TABLE status (
+	status_id int NOT NULL,
+	CONSTRAINT status_pkey PRIMARY KEY (status_id)
+);
+
+CREATE TABLE news (
+	news_id int NOT NULL,
+	CONSTRAINT news_pkey PRIMARY KEY (news_id)
+);
+
+CREATE TABLE people (
+	person_id int NOT NULL,
+	CONSTRAINT people_pkey PRIMARY KEY (person_id)
+);
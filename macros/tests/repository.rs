@@ -0,0 +1,88 @@
+mod common;
+
+use actix_web::{delete, get, patch, post, web, HttpResponse};
+use macros::generate_structs_from_ddl;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgPool;
+
+generate_structs_from_ddl!("tests/models.sql");
+
+const SCHEMA: &str = "repository_test";
+
+#[actix_web::test]
+#[ignore = "needs DATABASE_URL"]
+async fn repository_runs_inside_a_transaction() {
+    let pool = common::test_pool(SCHEMA).await;
+
+    let mut tx = pool.begin().await.unwrap();
+    let new = NewAsset {
+        asset_id: None,
+        asset_name: String::from("laptop"),
+        asset_brand: None,
+    };
+    let asset = AssetRepository::insert(&mut *tx, &new).await.unwrap();
+    let key = AssetKey {
+        asset_id: asset.asset_id,
+    };
+
+    let found = AssetRepository::find_by_asset_name(&mut *tx, &String::from("laptop"))
+        .await
+        .unwrap();
+    assert_eq!(found.map(|a| a.asset_id), Some(asset.asset_id));
+
+    let patch = AssetPatch {
+        asset_name: Some(String::from("desktop")),
+        ..Default::default()
+    };
+    let updated = AssetRepository::update(&mut *tx, &key, &patch)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(updated.asset_name, "desktop");
+    assert_eq!(updated.created_at, asset.created_at);
+
+    // An empty patch leaves the row alone.
+    let unchanged = AssetRepository::update(&mut *tx, &key, &AssetPatch::default())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(unchanged.asset_name, "desktop");
+
+    assert_eq!(AssetRepository::list(&mut *tx).await.unwrap().len(), 1);
+    tx.rollback().await.unwrap();
+
+    assert!(AssetRepository::get_by_pk(&pool, &key)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(!AssetRepository::delete(&pool, &key).await.unwrap());
+
+    sqlx::raw_sql(
+        "INSERT INTO assets (asset_id, asset_name)
+             VALUES ('00000000-0000-0000-0000-000000000000', 'tablet');
+         INSERT INTO collections (collection_id, collection_name)
+             VALUES ('ffffffff-ffff-ffff-ffff-ffffffffffff', 'office');",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let link = AssetCollectionRepository::insert(
+        &pool,
+        &NewAssetCollection {
+            asset_id: uuid::Uuid::nil(),
+            collection_id: uuid::Uuid::max(),
+            position: None,
+        },
+    )
+    .await
+    .unwrap();
+    let link_key = AssetCollectionKey {
+        asset_id: link.asset_id,
+        collection_id: link.collection_id,
+    };
+    assert!(AssetCollectionRepository::delete(&pool, &link_key)
+        .await
+        .unwrap());
+
+    common::drop_schema(&pool, SCHEMA).await;
+}
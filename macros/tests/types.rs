@@ -0,0 +1,84 @@
+use actix_web::{delete, get, patch, post, web, HttpResponse};
+use macros::generate_structs_from_ddl;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::types::{PgInterval, PgMoney, PgRange, PgTimeTz};
+use sqlx::postgres::PgPool;
+use std::ops::Bound;
+
+generate_structs_from_ddl!("tests/types.sql");
+
+#[test]
+fn postgres_types_map_to_rust_types() {
+    // Each field is given a value of the Rust type it is expected to map to,
+    // so a wrong mapping fails to compile.
+    let row = ColumnType {
+        small_col: 1i16,
+        int_col: Some(1i32),
+        big_col: Some(1i64),
+        serial_col: Some(1i64),
+        real_col: Some(1.0f32),
+        double_col: Some(1.0f64),
+        numeric_col: Some(rust_decimal::Decimal::new(1050, 2)),
+        text_col: Some(String::from("text")),
+        varchar_col: Some(String::from("varchar")),
+        bool_col: Some(true),
+        bytes_col: Some(vec![0u8, 1]),
+        uuid_col: Some(uuid::Uuid::nil()),
+        json_col: Some(serde_json::json!({ "a": 1 })),
+        date_col: Some(chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()),
+        time_col: Some(chrono::NaiveTime::from_hms_opt(12, 0, 0).unwrap()),
+        timestamp_col: Some(chrono::NaiveDateTime::default()),
+        timestamptz_col: Some(chrono::DateTime::<chrono::Utc>::default()),
+        long_timestamptz_col: Some(chrono::DateTime::<chrono::Utc>::default()),
+        inet_col: Some("10.0.0.0/8".parse::<ipnetwork::IpNetwork>().unwrap()),
+        text_array_col: Some(vec![String::from("a")]),
+        int_array_col: Some(vec![1i32]),
+        status_col: Some(AssetStatus::InReview),
+        oid_col: Some(sqlx::postgres::types::Oid(1)),
+        float_col: Some(1.0f32),
+        interval_col: Some(pg_types::Interval(PgInterval {
+            months: 1,
+            days: 2,
+            microseconds: 3,
+        })),
+        money_col: Some(pg_types::Money(PgMoney(1050))),
+        timetz_col: Some(pg_types::TimeTz(PgTimeTz {
+            time: chrono::NaiveTime::from_hms_opt(12, 0, 0).unwrap(),
+            offset: chrono::FixedOffset::east_opt(3600).unwrap(),
+        })),
+        int_range_col: Some(pg_types::Range(PgRange {
+            start: Bound::Included(1i32),
+            end: Bound::Excluded(5),
+        })),
+        tstz_range_col: Some(pg_types::Range(PgRange {
+            start: Bound::Included(chrono::DateTime::<chrono::Utc>::default()),
+            end: Bound::Unbounded,
+        })),
+        r#type: Some(String::from("keyword")),
+        self_: Some(String::from("reserved")),
+    };
+
+    let json = serde_json::to_value(&row).unwrap();
+    assert_eq!(json["status_col"], "in review");
+    assert_eq!(json["uuid_col"], "00000000-0000-0000-0000-000000000000");
+    assert_eq!(
+        json["interval_col"],
+        serde_json::json!({ "months": 1, "days": 2, "microseconds": 3 })
+    );
+    assert_eq!(json["money_col"], 1050);
+    assert_eq!(
+        json["timetz_col"],
+        serde_json::json!({ "time": "12:00:00", "offset_seconds": 3600 })
+    );
+    assert_eq!(
+        json["int_range_col"],
+        serde_json::json!({ "start": { "Included": 1 }, "end": { "Excluded": 5 } })
+    );
+    // Keyword columns keep their names in JSON.
+    assert_eq!(json["type"], "keyword");
+    assert_eq!(json["self"], "reserved");
+    let parsed: ColumnType = serde_json::from_value(json).unwrap();
+    assert_eq!(parsed.r#type.as_deref(), Some("keyword"));
+    assert_eq!(parsed.int_range_col, row.int_range_col);
+    assert_eq!(parsed.timetz_col, row.timetz_col);
+}
@@ -0,0 +1,36 @@
+CREATE TYPE asset_status AS ENUM ('draft', 'in review', 'published');
+
+CREATE TABLE column_types (
+	small_col int2 NOT NULL,
+	int_col INTEGER,
+	big_col int8,
+	serial_col bigserial,
+	real_col float4,
+	double_col double precision,
+	numeric_col numeric(10, 2),
+	text_col text,
+	varchar_col varchar(255),
+	bool_col BOOL,
+	bytes_col bytea,
+	uuid_col uuid,
+	json_col jsonb,
+	date_col date,
+	time_col time,
+	timestamp_col timestamp,
+	timestamptz_col timestamptz,
+	long_timestamptz_col timestamp(3) with time zone,
+	inet_col inet,
+	text_array_col _text,
+	int_array_col int4[],
+	status_col asset_status,
+	oid_col oid,
+	float_col float(10),
+	interval_col interval,
+	money_col money,
+	timetz_col timetz,
+	int_range_col int4range,
+	tstz_range_col tstzrange,
+	type text,
+	self text,
+	PRIMARY KEY (small_col)
+);
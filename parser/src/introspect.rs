@@ -0,0 +1,189 @@
+//! Builds a [`Schema`] from a live Postgres database by reading
+//! `information_schema` and `pg_catalog`, as an alternative to parsing DDL.
+//!
+//! The result can be saved with [`Schema::write_snapshot`] and handed to
+//! `generate_structs_from_ddl!("schema.json")`, so builds do not need a database.
+
+use crate::{Column, Constraint, Enum, ForeignKey, Index, PrimaryKey, Schema, Table, Unique};
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use std::path::Path;
+
+const TABLES: &str = "
+    SELECT t.table_name::text,
+           obj_description(format('%I.%I', t.table_schema, t.table_name)::regclass, 'pg_class')
+    FROM information_schema.tables t
+    WHERE t.table_schema = $1 AND t.table_type = 'BASE TABLE'
+    ORDER BY t.table_name";
+
+const COLUMNS: &str = "
+    SELECT c.table_name::text,
+           c.column_name::text,
+           c.udt_name::text,
+           c.column_default::text,
+           c.is_nullable = 'NO',
+           col_description(format('%I.%I', c.table_schema, c.table_name)::regclass,
+                           c.ordinal_position::int)
+    FROM information_schema.columns c
+    JOIN information_schema.tables t
+      ON t.table_schema = c.table_schema AND t.table_name = c.table_name
+    WHERE c.table_schema = $1 AND t.table_type = 'BASE TABLE'
+    ORDER BY c.table_name, c.ordinal_position";
+
+const CONSTRAINTS: &str = "
+    SELECT rel.relname::text,
+           con.conname::text,
+           con.contype::text,
+           ARRAY(SELECT a.attname::text
+                 FROM unnest(con.conkey) WITH ORDINALITY AS k(attnum, ord)
+                 JOIN pg_attribute a ON a.attrelid = con.conrelid AND a.attnum = k.attnum
+                 ORDER BY k.ord),
+           target.relname::text,
+           ARRAY(SELECT a.attname::text
+                 FROM unnest(con.confkey) WITH ORDINALITY AS k(attnum, ord)
+                 JOIN pg_attribute a ON a.attrelid = con.confrelid AND a.attnum = k.attnum
+                 ORDER BY k.ord)
+    FROM pg_constraint con
+    JOIN pg_class rel ON rel.oid = con.conrelid
+    JOIN pg_namespace ns ON ns.oid = rel.relnamespace
+    LEFT JOIN pg_class target ON target.oid = con.confrelid
+    WHERE ns.nspname = $1 AND con.contype IN ('p', 'u', 'f')
+    ORDER BY rel.relname, con.conname";
+
+// Indexes backing PRIMARY KEY and UNIQUE constraints are already described by
+// the constraints themselves.
+const INDEXES: &str = "
+    SELECT rel.relname::text,
+           idx.relname::text,
+           i.indisunique,
+           ARRAY(SELECT a.attname::text
+                 FROM unnest(i.indkey::int2[]) WITH ORDINALITY AS k(attnum, ord)
+                 JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = k.attnum
+                 ORDER BY k.ord)
+    FROM pg_index i
+    JOIN pg_class idx ON idx.oid = i.indexrelid
+    JOIN pg_class rel ON rel.oid = i.indrelid
+    JOIN pg_namespace ns ON ns.oid = rel.relnamespace
+    WHERE ns.nspname = $1
+      AND NOT EXISTS (SELECT 1 FROM pg_constraint con WHERE con.conindid = i.indexrelid)
+    ORDER BY rel.relname, idx.relname";
+
+const ENUMS: &str = "
+    SELECT t.typname::text, array_agg(e.enumlabel::text ORDER BY e.enumsortorder)
+    FROM pg_type t
+    JOIN pg_enum e ON e.enumtypid = t.oid
+    JOIN pg_namespace ns ON ns.oid = t.typnamespace
+    WHERE ns.nspname = $1
+    GROUP BY t.typname
+    ORDER BY t.typname";
+
+type ColumnRow = (String, String, String, Option<String>, bool, Option<String>);
+type ConstraintRow = (
+    String,
+    String,
+    String,
+    Vec<String>,
+    Option<String>,
+    Vec<String>,
+);
+
+/// Reads every table, constraint, index and enum type in `schema_name`.
+pub async fn introspect(pool: &PgPool, schema_name: &str) -> Result<Schema, sqlx::Error> {
+    let mut schema = Schema::new();
+
+    let tables: Vec<(String, Option<String>)> = sqlx::query_as(TABLES)
+        .bind(schema_name)
+        .fetch_all(pool)
+        .await?;
+    for (name, comment) in tables {
+        schema.tables.push(Table {
+            name,
+            columns: Vec::new(),
+            constraints: Vec::new(),
+            indexes: Vec::new(),
+            comment,
+        });
+    }
+
+    let columns: Vec<ColumnRow> = sqlx::query_as(COLUMNS)
+        .bind(schema_name)
+        .fetch_all(pool)
+        .await?;
+    for (table, name, dtype, default, not_null, comment) in columns {
+        if let Some(table) = schema.tables.iter_mut().find(|t| t.name == table) {
+            table.columns.push(Column {
+                name,
+                dtype,
+                default,
+                options: if not_null {
+                    vec!["NOT NULL".to_string()]
+                } else {
+                    Vec::new()
+                },
+                comment,
+            });
+        }
+    }
+
+    let constraints: Vec<ConstraintRow> = sqlx::query_as(CONSTRAINTS)
+        .bind(schema_name)
+        .fetch_all(pool)
+        .await?;
+    for (table, name, kind, columns, target_table, target_columns) in constraints {
+        let name = Some(name);
+        let constraint = match kind.as_str() {
+            "p" => Constraint::PrimaryKey(PrimaryKey { name, columns }),
+            "u" => Constraint::Unique(Unique { name, columns }),
+            _ => Constraint::ForeignKey(ForeignKey {
+                name,
+                source_columns: columns,
+                target_table: target_table.unwrap_or_default(),
+                target_columns,
+            }),
+        };
+        if let Some(table) = schema.tables.iter_mut().find(|t| t.name == table) {
+            table.constraints.push(constraint);
+        }
+    }
+
+    let indexes: Vec<(String, String, bool, Vec<String>)> = sqlx::query_as(INDEXES)
+        .bind(schema_name)
+        .fetch_all(pool)
+        .await?;
+    for (table, name, unique, columns) in indexes {
+        if let Some(table) = schema.tables.iter_mut().find(|t| t.name == table) {
+            table.indexes.push(Index {
+                name,
+                columns,
+                unique,
+            });
+        }
+    }
+
+    let enums: Vec<(String, Vec<String>)> = sqlx::query_as(ENUMS)
+        .bind(schema_name)
+        .fetch_all(pool)
+        .await?;
+    schema.enums = enums
+        .into_iter()
+        .map(|(name, values)| Enum { name, values })
+        .collect();
+
+    Ok(schema)
+}
+
+/// Connects to `database_url`, introspects `schema_name` and writes the JSON
+/// snapshot to `path`.
+pub async fn write_snapshot<P: AsRef<Path>>(
+    database_url: &str,
+    schema_name: &str,
+    path: P,
+) -> Result<Schema, sqlx::Error> {
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(database_url)
+        .await?;
+    let schema = introspect(&pool, schema_name).await?;
+    pool.close().await;
+    schema.write_snapshot(path)?;
+    Ok(schema)
+}
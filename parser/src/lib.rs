@@ -1,18 +1,28 @@
 use std::fmt;
 use std::path::PathBuf;
 use std::sync::Arc;
+
+#[cfg(feature = "introspect")]
+pub mod introspect;
+mod schema;
+pub use schema::{migration_files, migration_version, split_statements, Schema, SchemaError};
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Column {
     pub name: String,
     pub dtype: String,
     pub default: Option<String>,
     pub options: Vec<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub comment: Option<String>,
 }
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Constraint {
     PrimaryKey(PrimaryKey),
     ForeignKey(ForeignKey),
     Unique(Unique),
+    Check(Check),
 }
 #[derive(Debug, Clone)]
 pub struct ParseError(String);
@@ -30,27 +40,102 @@ impl fmt::Display for ParseError {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Unique {
+    pub name: Option<String>,
     pub columns: Vec<String>,
 }
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PrimaryKey {
+    pub name: Option<String>,
     pub columns: Vec<String>,
 }
 
+/// A `CHECK` constraint, kept so it can be dropped or renamed by name.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Check {
+    pub name: Option<String>,
+    pub expression: String,
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ForeignKey {
+    pub name: Option<String>,
     pub source_columns: Vec<String>,
     pub target_table: String,
     pub target_columns: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Index {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub unique: bool,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Enum {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Table {
     pub name: String,
     pub columns: Vec<Column>,
     pub constraints: Vec<Constraint>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub indexes: Vec<Index>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub comment: Option<String>,
+}
+impl Column {
+    /// True when the column was declared (or later altered to be) `NOT NULL`.
+    pub fn is_not_null(&self) -> bool {
+        self.options
+            .iter()
+            .any(|o| o.eq_ignore_ascii_case("NOT NULL"))
+    }
+
+    /// True when Postgres fills the column in on insert, either from a
+    /// `DEFAULT` or because it is a `serial` type.
+    pub fn has_default(&self) -> bool {
+        let dtype = self.dtype.to_lowercase();
+        self.default.is_some()
+            || matches!(
+                dtype.as_str(),
+                "smallserial" | "serial2" | "serial" | "serial4" | "bigserial" | "serial8"
+            )
+    }
+}
+
+impl Constraint {
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Constraint::PrimaryKey(pk) => pk.name.as_deref(),
+            Constraint::ForeignKey(fk) => fk.name.as_deref(),
+            Constraint::Unique(u) => u.name.as_deref(),
+            Constraint::Check(c) => c.name.as_deref(),
+        }
+    }
+
+    fn named(self, name: &str) -> Self {
+        let name = Some(name.to_string());
+        match self {
+            Constraint::PrimaryKey(pk) => Constraint::PrimaryKey(PrimaryKey { name, ..pk }),
+            Constraint::ForeignKey(fk) => Constraint::ForeignKey(ForeignKey { name, ..fk }),
+            Constraint::Unique(u) => Constraint::Unique(Unique { name, ..u }),
+            Constraint::Check(c) => Constraint::Check(Check { name, ..c }),
+        }
+    }
 }
+
 #[derive(Debug, Clone)]
 pub enum ConstraintOrColumn {
     Constraint(Constraint),
@@ -295,6 +380,7 @@ pub fn primary_key<'a>() -> Parser<'a, ConstraintOrColumn> {
                 move |defs| {
                     match_char(')').map(move |_| {
                         ConstraintOrColumn::Constraint(Constraint::PrimaryKey(PrimaryKey {
+                            name: None,
                             columns: defs.to_vec().into_iter().map(|s| s.to_string()).collect(),
                         }))
                     })
@@ -310,6 +396,7 @@ pub fn unique<'a>() -> Parser<'a, ConstraintOrColumn> {
                 move |defs| {
                     match_char(')').map(move |_| {
                         ConstraintOrColumn::Constraint(Constraint::Unique(Unique {
+                            name: None,
                             columns: defs.to_vec().into_iter().map(|s| s.to_string()).collect(),
                         }))
                     })
@@ -320,10 +407,58 @@ pub fn unique<'a>() -> Parser<'a, ConstraintOrColumn> {
 
 pub fn constraint<'a>() -> Parser<'a, ConstraintOrColumn> {
     with_whitespace(match_string("CONSTRAINT")).and_then({
-        move |_| with_whitespace(name()).and_then(|_| foreign_key().or(primary_key()).or(unique()))
+        move |_| {
+            with_whitespace(name()).and_then(|constraint_name| {
+                foreign_key()
+                    .or(primary_key())
+                    .or(unique())
+                    .or(check())
+                    .map(move |parsed| match parsed {
+                        ConstraintOrColumn::Constraint(c) => {
+                            ConstraintOrColumn::Constraint(c.named(constraint_name))
+                        }
+                        other => other,
+                    })
+            })
+        }
     })
 }
 
+// The text up to the `)` closing an already consumed `(`, skipping over
+// nested parentheses and quoted strings.
+fn parenthesized<'a>() -> Parser<'a, &'a str> {
+    Parser::new(|input: &'a str| {
+        let mut depth = 0;
+        let mut quote = None;
+        for (i, c) in input.char_indices() {
+            match (quote, c) {
+                (Some(q), c) if c == q => quote = None,
+                (Some(_), _) => {}
+                (None, '\'' | '"') => quote = Some(c),
+                (None, '(') => depth += 1,
+                (None, ')') if depth == 0 => return Ok((&input[..i], &input[i + 1..])),
+                (None, ')') => depth -= 1,
+                _ => {}
+            }
+        }
+        Err(ParseError(format!("Unbalanced parentheses: {}", input)))
+    })
+}
+
+pub fn check<'a>() -> Parser<'a, ConstraintOrColumn> {
+    with_whitespace(keyword("CHECK"))
+        .and_then(|_| match_char('('))
+        .and_then(|_| parenthesized())
+        .and_then(|expression| {
+            optional(with_whitespace(keyword("NO INHERIT"))).map(move |_| {
+                ConstraintOrColumn::Constraint(Constraint::Check(Check {
+                    name: None,
+                    expression: expression.trim().to_string(),
+                }))
+            })
+        })
+}
+
 pub fn schema_name_table_name<'a>() -> Parser<'a, &'a str> {
     with_whitespace(name())
         .and_then(move |_| with_whitespace(match_char('.')).and_then(|_| name()))
@@ -358,6 +493,7 @@ pub fn foreign_key<'a>() -> Parser<'a, ConstraintOrColumn> {
 
                                                                 move |_| {
                                                                ConstraintOrColumn::Constraint(Constraint::ForeignKey(ForeignKey {
+                                                                    name: None,
                                                                     source_columns: def6
                                                                         .to_vec()
                                                                         .into_iter()
@@ -386,70 +522,199 @@ pub fn foreign_key<'a>() -> Parser<'a, ConstraintOrColumn> {
             })
     })
 }
+// `ON DELETE` / `ON UPDATE` actions of a foreign key, which the schema does
+// not record.
 pub fn cascade<'a>() -> Parser<'a, &'a str> {
-    with_whitespace(match_string("ON DELETE CASCADE"))
-        .and_then({
-            move |_| {
-                with_whitespace(match_string("ON UPDATE CASCADE"))
-                    .or(Parser::new(|input| Ok(("", input))))
+    let action = with_whitespace(keyword("ON DELETE"))
+        .or(with_whitespace(keyword("ON UPDATE")))
+        .and_then(|_| {
+            with_whitespace(keyword("CASCADE"))
+                .or(with_whitespace(keyword("RESTRICT")))
+                .or(with_whitespace(keyword("NO ACTION")))
+                .or(with_whitespace(keyword("SET NULL")))
+                .or(with_whitespace(keyword("SET DEFAULT")))
+        });
+    action.zero_or_more().map(|_| "")
+}
+
+// A column's inline `REFERENCES brands (brand_id) ON DELETE CASCADE`. Without
+// a column list the target's primary key is meant, see `Schema::apply`.
+fn references<'a>() -> Parser<'a, ForeignKey> {
+    with_whitespace(keyword("REFERENCES"))
+        .and_then(|_| with_whitespace(schema_name_table_name()))
+        .and_then(|table_name| {
+            with_whitespace(match_char('('))
+                .and_then(|_| comma_sep(with_whitespace(name())))
+                .and_then(|columns| {
+                    with_whitespace(match_char(')'))
+                        .map(move |_| columns.iter().map(|c| c.to_string()).collect())
+                })
+                .or(Parser::new(|input| Ok((Vec::new(), input))))
+                .and_then(move |target_columns: Vec<String>| {
+                    cascade().map(move |_| ForeignKey {
+                        name: None,
+                        source_columns: Vec::new(),
+                        target_table: table_name.to_string(),
+                        target_columns: target_columns.clone(),
+                    })
+                })
+        })
+}
+
+// A column type such as `text`, `varchar(255)`, `numeric(10, 2)`,
+// `double precision`, `timestamp(3) with time zone` or `text[]`, with
+// whitespace inside the type normalised to single spaces.
+pub fn data_type<'a>() -> Parser<'a, String> {
+    name().and_then(|base| {
+        let words = with_whitespace(keyword("PRECISION"))
+            .or(with_whitespace(keyword("VARYING")))
+            .or(with_whitespace(keyword("WITH TIME ZONE")))
+            .or(with_whitespace(keyword("WITHOUT TIME ZONE")))
+            .map(|word| format!(" {}", word.to_lowercase()));
+        let modifiers = with_whitespace(match_char('('))
+            .and_then(|_| comma_sep(with_whitespace(number())))
+            .and_then(|args| {
+                with_whitespace(match_char(')')).map(move |_| format!("({})", args.join(",")))
+            });
+        let array = with_whitespace(match_string("[]")).map(|s| s.to_string());
+        words
+            .or(modifiers)
+            .or(array)
+            .zero_or_more()
+            .map(move |parts| base.to_string() + &parts.concat())
+    })
+}
+
+#[derive(Debug, Clone)]
+enum ColumnModifier {
+    Default(String),
+    Option(String),
+    Null,
+    Constraint(Constraint),
+}
+
+// A DEFAULT expression as written: `now()`, `-1`, `'draft'::character varying`
+// or `nextval('assets_id_seq'::regclass)`. It ends at the first space, comma
+// or unmatched `)` outside of quotes, after any `::` casts.
+pub fn default_value<'a>() -> Parser<'a, &'a str> {
+    Parser::new(|input: &'a str| {
+        let mut depth = 0;
+        let mut quote = None;
+        let mut end = input.len();
+        for (i, c) in input.char_indices() {
+            match (quote, c) {
+                (Some(q), c) if c == q => quote = None,
+                (Some(_), _) => {}
+                (None, '\'' | '"') => quote = Some(c),
+                (None, '(') => depth += 1,
+                (None, ')') if depth > 0 => depth -= 1,
+                (None, ':') if depth == 0 && input[i..].starts_with("::") => {
+                    end = i;
+                    break;
+                }
+                (None, c) if depth == 0 && (c == ')' || c == ',' || c.is_whitespace()) => {
+                    end = i;
+                    break;
+                }
+                _ => {}
             }
+        }
+        if quote.is_some() || depth > 0 || end == 0 {
+            return Err(ParseError(format!(
+                "Found invalid input while looking for default: {}",
+                input
+            )));
+        }
+        let mut rest = &input[end..];
+        while let Some(cast) = rest.strip_prefix("::") {
+            let (_, after) = data_type().parse(cast)?;
+            rest = after;
+        }
+        let value = input[..input.len() - rest.len()].trim_end();
+        Ok((value, &input[value.len()..]))
+    })
+}
+
+// DEFAULT, NOT NULL, PRIMARY KEY, UNIQUE, REFERENCES and CHECK may appear in
+// any order after the type.
+fn column_modifier<'a>() -> Parser<'a, ColumnModifier> {
+    with_whitespace(match_string("DEFAULT"))
+        .and_then(|_| {
+            with_whitespace(default_value()).map(|val| ColumnModifier::Default(val.to_string()))
         })
-        .or(with_whitespace(match_string("ON UPDATE CASCADE")))
-        .or(Parser::new(|input| Ok(("", input))))
+        .or(with_whitespace(match_string("NOT NULL"))
+            .map(|s| ColumnModifier::Option(s.to_string())))
+        .or(with_whitespace(match_string("NULL")).map(|_| ColumnModifier::Null))
+        .or(with_whitespace(match_string("PRIMARY KEY"))
+            .map(|s| ColumnModifier::Option(s.to_string())))
+        .or(with_whitespace(match_string("UNIQUE")).map(|s| ColumnModifier::Option(s.to_string())))
+        .or(references().map(|fk| ColumnModifier::Constraint(Constraint::ForeignKey(fk))))
+        .or(check().map(|parsed| match parsed {
+            ConstraintOrColumn::Constraint(c) => ColumnModifier::Constraint(c),
+            ConstraintOrColumn::Column(_) => ColumnModifier::Null,
+        }))
 }
 
-pub fn column<'a>() -> Parser<'a, ConstraintOrColumn> {
+/// A column followed by the table constraints declared inline on it, such as
+/// the foreign key of `brand_id text REFERENCES brands (brand_id)`.
+pub fn column_definition<'a>() -> Parser<'a, Vec<ConstraintOrColumn>> {
     with_whitespace(name()).and_then(|colname| {
-        with_whitespace(name()).and_then(move |dtype| {
-            // Capture default value if present
-            let default_parser = with_whitespace(match_string("DEFAULT"))
-                .and_then(|_| {
-                    with_whitespace(function())
-                        .or(with_whitespace(number()))
-                        .or(with_whitespace(name()))
-                        .map(|val| Ok(val.to_string()))
-                })
-                .or(Parser::new(|input| Ok((Err("No default value"), input))));
-
-            default_parser.and_then(move |default_value| {
-                // Capture NOT NULL constraint if present
-                let not_null_parser = with_whitespace(match_string("NOT NULL"))
-                    .or(with_whitespace(match_string("NULL")))
-                    .or(Parser::new(|input| Ok(("", input)))); // Changed to return (bool, &str)
-
-                not_null_parser.and_then({
-                    let value = default_value.clone().ok();
-                    move |not_null| {
-                        // Capture other constraints
-                        let constraint_parser = with_whitespace(match_string("PRIMARY KEY"))
-                            .or(with_whitespace(match_string("UNIQUE")))
-                            .zero_or_more();
-
-                        constraint_parser.map({
-                            let value_final = value.clone();
-                            move |constraints| {
-                                ConstraintOrColumn::Column(Column {
-                                    name: colname.to_string(),
-                                    dtype: dtype.to_string(),
-                                    default: value_final.clone(), // No need to clone here
-                                    options: constraints
-                                        .into_iter()
-                                        .map(|s| s.to_string())
-                                        .collect(),
-                                })
-                            }
-                        })
+        with_whitespace(data_type()).and_then(move |dtype| {
+            column_modifier().zero_or_more().map(move |modifiers| {
+                let mut default = None;
+                let mut options = Vec::new();
+                let mut constraints = Vec::new();
+                for modifier in modifiers {
+                    match modifier {
+                        ColumnModifier::Default(value) => default = Some(value),
+                        ColumnModifier::Option(option) => options.push(option),
+                        ColumnModifier::Null => {}
+                        ColumnModifier::Constraint(Constraint::ForeignKey(fk)) => {
+                            constraints.push(Constraint::ForeignKey(ForeignKey {
+                                source_columns: vec![colname.to_string()],
+                                ..fk
+                            }))
+                        }
+                        ColumnModifier::Constraint(c) => constraints.push(c),
                     }
-                })
+                }
+                let column = ConstraintOrColumn::Column(Column {
+                    name: colname.to_string(),
+                    dtype: dtype.clone(),
+                    default,
+                    options,
+                    comment: None,
+                });
+                std::iter::once(column)
+                    .chain(constraints.into_iter().map(ConstraintOrColumn::Constraint))
+                    .collect()
             })
         })
     })
 }
 
+/// Just the column of a [`column_definition`].
+pub fn column<'a>() -> Parser<'a, ConstraintOrColumn> {
+    column_definition().map(|mut parsed| parsed.remove(0))
+}
+
 pub fn column_list<'a>() -> Parser<'a, Arc<Vec<ConstraintOrColumn>>> {
     with_whitespace(match_char('('))
-        .and_then(|_| comma_sep(constraint().or(column())))
-        .and_then(move |cols| with_whitespace(match_char(')')).map(move |_| Arc::clone(&cols)))
+        .and_then(|_| {
+            comma_sep(
+                constraint()
+                    .or(primary_key())
+                    .or(foreign_key())
+                    .or(unique())
+                    .or(check())
+                    .map(|c| vec![c])
+                    .or(column_definition()),
+            )
+        })
+        .and_then(move |cols| {
+            with_whitespace(match_char(')'))
+                .map(move |_| Arc::new(cols.iter().flatten().cloned().collect()))
+        })
 }
 
 pub fn comma_sep<'a, Output: 'a>(parser: Parser<'a, Output>) -> Parser<'a, Arc<Vec<Output>>> {
@@ -471,6 +736,7 @@ pub fn comma_sep<'a, Output: 'a>(parser: Parser<'a, Output>) -> Parser<'a, Arc<V
 
 pub fn create_table_parser<'a>() -> Parser<'a, Table> {
     with_whitespace(match_string("CREATE TABLE"))
+        .and_then(|_| optional(with_whitespace(keyword("IF NOT EXISTS"))))
         .and_then(move |_| {
             with_whitespace(name())
                 .and_then(|_| {
@@ -492,7 +758,417 @@ pub fn create_table_parser<'a>() -> Parser<'a, Table> {
                     name: table_name.to_string(),
                     columns: column_defs.to_vec(),
                     constraints: constraints.to_vec(),
+                    indexes: Vec::new(),
+                    comment: None,
                 }
             })
         })
 }
+
+#[derive(Debug, Clone)]
+pub enum AlterTableAction {
+    AddColumn(Column),
+    DropColumn(String),
+    RenameColumn { from: String, to: String },
+    RenameTo(String),
+    AlterColumnType { column: String, dtype: String },
+    SetDefault { column: String, default: String },
+    DropDefault(String),
+    SetNotNull(String),
+    DropNotNull(String),
+    AddConstraint(Constraint),
+    DropConstraint(String),
+    RenameConstraint { from: String, to: String },
+}
+
+#[derive(Debug, Clone)]
+pub enum Statement {
+    CreateTable(Table),
+    AlterTable {
+        table: String,
+        actions: Vec<AlterTableAction>,
+    },
+    DropTable(Vec<String>),
+    CreateEnum(Enum),
+    AddEnumValue {
+        name: String,
+        value: String,
+    },
+    DropType(Vec<String>),
+    CreateIndex {
+        table: String,
+        index: Index,
+    },
+    DropIndex(Vec<String>),
+    Comment {
+        target: CommentTarget,
+        comment: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub enum CommentTarget {
+    Table(String),
+    Column { table: String, column: String },
+}
+
+// Like `match_string`, but refuses to match the prefix of a longer identifier,
+// so `RENAME TO` does not swallow `RENAME total_cost TO ...`.
+pub fn keyword<'a>(expected: &'a str) -> Parser<'a, &'a str> {
+    Parser::new(move |input: &'a str| {
+        let (matched, rest) = match_string(expected).parse(input)?;
+        match rest.chars().next() {
+            Some(c) if c.is_alphanumeric() || c == '_' => Err(ParseError(format!(
+                "Found invalid input while looking for keyword {}: {}",
+                expected, input
+            ))),
+            _ => Ok((matched, rest)),
+        }
+    })
+}
+
+pub fn optional<'a>(parser: Parser<'a, &'a str>) -> Parser<'a, &'a str> {
+    parser.or(Parser::new(|input| Ok(("", input))))
+}
+
+fn drop_behaviour<'a>() -> Parser<'a, &'a str> {
+    optional(with_whitespace(keyword("CASCADE")).or(with_whitespace(keyword("RESTRICT"))))
+}
+
+pub fn drop_table_parser<'a>() -> Parser<'a, Statement> {
+    with_whitespace(keyword("DROP TABLE"))
+        .and_then(|_| optional(with_whitespace(keyword("IF EXISTS"))))
+        .and_then(|_| comma_sep(with_whitespace(schema_name_table_name())))
+        .and_then(|tables| {
+            drop_behaviour()
+                .map(move |_| Statement::DropTable(tables.iter().map(|t| t.to_string()).collect()))
+        })
+}
+
+// One action per added column or constraint, so a column's inline foreign key
+// follows it as an `AddConstraint`.
+fn add_action<'a>() -> Parser<'a, Vec<AlterTableAction>> {
+    let add_column = with_whitespace(keyword("ADD COLUMN"))
+        .and_then(|_| optional(with_whitespace(keyword("IF NOT EXISTS"))))
+        .and_then(|_| column_definition());
+    let add_other = with_whitespace(keyword("ADD")).and_then(|_| {
+        constraint()
+            .or(primary_key())
+            .or(foreign_key())
+            .or(unique())
+            .or(check())
+            .map(|c| vec![c])
+            .or(column_definition())
+    });
+    add_column.or(add_other).map(|parsed| {
+        parsed
+            .into_iter()
+            .map(|parsed| match parsed {
+                ConstraintOrColumn::Column(c) => AlterTableAction::AddColumn(c),
+                ConstraintOrColumn::Constraint(c) => AlterTableAction::AddConstraint(c),
+            })
+            .collect()
+    })
+}
+
+fn drop_action<'a>() -> Parser<'a, AlterTableAction> {
+    let drop_constraint = with_whitespace(keyword("DROP CONSTRAINT"))
+        .and_then(|_| optional(with_whitespace(keyword("IF EXISTS"))))
+        .and_then(|_| with_whitespace(name()))
+        .and_then(|constraint_name| {
+            drop_behaviour()
+                .map(move |_| AlterTableAction::DropConstraint(constraint_name.to_string()))
+        });
+    let drop_column = with_whitespace(keyword("DROP COLUMN"))
+        .or(with_whitespace(keyword("DROP")))
+        .and_then(|_| optional(with_whitespace(keyword("IF EXISTS"))))
+        .and_then(|_| with_whitespace(name()))
+        .and_then(|column_name| {
+            drop_behaviour().map(move |_| AlterTableAction::DropColumn(column_name.to_string()))
+        });
+    drop_constraint.or(drop_column)
+}
+
+fn rename_action<'a>() -> Parser<'a, AlterTableAction> {
+    let rename_to = with_whitespace(keyword("RENAME TO"))
+        .and_then(|_| with_whitespace(name()))
+        .map(|new_name| AlterTableAction::RenameTo(new_name.to_string()));
+    let rename_constraint = with_whitespace(keyword("RENAME CONSTRAINT"))
+        .and_then(|_| with_whitespace(name()))
+        .and_then(|from| {
+            with_whitespace(keyword("TO"))
+                .and_then(|_| with_whitespace(name()))
+                .map(move |to| AlterTableAction::RenameConstraint {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                })
+        });
+    let rename_column = with_whitespace(keyword("RENAME COLUMN"))
+        .or(with_whitespace(keyword("RENAME")))
+        .and_then(|_| with_whitespace(name()))
+        .and_then(|from| {
+            with_whitespace(keyword("TO"))
+                .and_then(|_| with_whitespace(name()))
+                .map(move |to| AlterTableAction::RenameColumn {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                })
+        });
+    rename_to.or(rename_constraint).or(rename_column)
+}
+
+fn alter_column_action<'a>() -> Parser<'a, AlterTableAction> {
+    with_whitespace(keyword("ALTER COLUMN"))
+        .or(with_whitespace(keyword("ALTER")))
+        .and_then(|_| with_whitespace(name()))
+        .and_then(|column_name| {
+            let column_name = column_name.to_string();
+            let set_type = with_whitespace(keyword("SET DATA TYPE"))
+                .or(with_whitespace(keyword("TYPE")))
+                .and_then(|_| with_whitespace(data_type()))
+                .and_then({
+                    let column_name = column_name.clone();
+                    move |dtype| {
+                        let column_name = column_name.clone();
+                        // A USING clause only affects how existing rows are converted.
+                        optional(with_whitespace(keyword("USING")).and_then(|_| until())).map(
+                            move |_| AlterTableAction::AlterColumnType {
+                                column: column_name.clone(),
+                                dtype: dtype.to_string(),
+                            },
+                        )
+                    }
+                });
+            let set_default = with_whitespace(keyword("SET DEFAULT"))
+                .and_then(|_| with_whitespace(default_value()))
+                .map({
+                    let column_name = column_name.clone();
+                    move |default| AlterTableAction::SetDefault {
+                        column: column_name.clone(),
+                        default: default.to_string(),
+                    }
+                });
+            let drop_default = with_whitespace(keyword("DROP DEFAULT")).map({
+                let column_name = column_name.clone();
+                move |_| AlterTableAction::DropDefault(column_name.clone())
+            });
+            let set_not_null = with_whitespace(keyword("SET NOT NULL")).map({
+                let column_name = column_name.clone();
+                move |_| AlterTableAction::SetNotNull(column_name.clone())
+            });
+            let drop_not_null = with_whitespace(keyword("DROP NOT NULL"))
+                .map(move |_| AlterTableAction::DropNotNull(column_name.clone()));
+            set_type
+                .or(set_default)
+                .or(drop_default)
+                .or(set_not_null)
+                .or(drop_not_null)
+        })
+}
+
+pub fn alter_table_action<'a>() -> Parser<'a, Vec<AlterTableAction>> {
+    add_action().or(drop_action()
+        .or(rename_action())
+        .or(alter_column_action())
+        .map(|action| vec![action]))
+}
+
+pub fn alter_table_parser<'a>() -> Parser<'a, Statement> {
+    with_whitespace(keyword("ALTER TABLE"))
+        .and_then(|_| optional(with_whitespace(keyword("IF EXISTS"))))
+        .and_then(|_| optional(with_whitespace(keyword("ONLY"))))
+        .and_then(|_| with_whitespace(schema_name_table_name()))
+        .and_then(|table_name| {
+            comma_sep(alter_table_action()).map(move |actions| Statement::AlterTable {
+                table: table_name.to_string(),
+                actions: actions.iter().flatten().cloned().collect(),
+            })
+        })
+}
+
+pub fn string_literal<'a>() -> Parser<'a, String> {
+    Parser::new(|input: &'a str| {
+        let mut rest = input.strip_prefix('\'').ok_or_else(|| {
+            ParseError(format!(
+                "Found invalid input while looking for string literal: {}",
+                input
+            ))
+        })?;
+        let mut value = String::new();
+        while let Some(end) = rest.find('\'') {
+            value.push_str(&rest[..end]);
+            rest = &rest[end + 1..];
+            // '' is an escaped quote inside the literal
+            match rest.strip_prefix('\'') {
+                Some(remaining) => {
+                    value.push('\'');
+                    rest = remaining;
+                }
+                None => return Ok((value, rest)),
+            }
+        }
+        Err(ParseError(format!(
+            "Unterminated string literal: {}",
+            input
+        )))
+    })
+}
+
+// `schema.table.column` style names, returned segment by segment.
+pub fn qualified_name<'a>() -> Parser<'a, Vec<&'a str>> {
+    name().and_then(|first| {
+        match_char('.')
+            .and_then(|_| name())
+            .zero_or_more()
+            .map(move |rest| {
+                let mut segments = vec![first];
+                segments.extend(rest);
+                segments
+            })
+    })
+}
+
+pub fn create_enum_parser<'a>() -> Parser<'a, Statement> {
+    with_whitespace(keyword("CREATE TYPE"))
+        .and_then(|_| with_whitespace(schema_name_table_name()))
+        .and_then(|type_name| {
+            with_whitespace(keyword("AS ENUM"))
+                .and_then(|_| with_whitespace(match_char('(')))
+                .and_then(|_| comma_sep(with_whitespace(string_literal())))
+                .and_then(move |values| {
+                    with_whitespace(match_char(')')).map(move |_| {
+                        Statement::CreateEnum(Enum {
+                            name: type_name.to_string(),
+                            values: values.to_vec(),
+                        })
+                    })
+                })
+        })
+}
+
+pub fn alter_enum_parser<'a>() -> Parser<'a, Statement> {
+    with_whitespace(keyword("ALTER TYPE"))
+        .and_then(|_| with_whitespace(schema_name_table_name()))
+        .and_then(|type_name| {
+            with_whitespace(keyword("ADD VALUE"))
+                .and_then(|_| optional(with_whitespace(keyword("IF NOT EXISTS"))))
+                .and_then(|_| with_whitespace(string_literal()))
+                .map(move |value| Statement::AddEnumValue {
+                    name: type_name.to_string(),
+                    value,
+                })
+        })
+}
+
+pub fn drop_type_parser<'a>() -> Parser<'a, Statement> {
+    with_whitespace(keyword("DROP TYPE"))
+        .and_then(|_| optional(with_whitespace(keyword("IF EXISTS"))))
+        .and_then(|_| comma_sep(with_whitespace(schema_name_table_name())))
+        .and_then(|types| {
+            drop_behaviour()
+                .map(move |_| Statement::DropType(types.iter().map(|t| t.to_string()).collect()))
+        })
+}
+
+pub fn create_index_parser<'a>() -> Parser<'a, Statement> {
+    with_whitespace(keyword("CREATE UNIQUE INDEX"))
+        .map(|_| true)
+        .or(with_whitespace(keyword("CREATE INDEX")).map(|_| false))
+        .and_then(|unique| {
+            optional(with_whitespace(keyword("CONCURRENTLY")))
+                .and_then(|_| optional(with_whitespace(keyword("IF NOT EXISTS"))))
+                .and_then(|_| with_whitespace(name()))
+                .and_then(move |index_name| {
+                    with_whitespace(keyword("ON"))
+                        .and_then(|_| optional(with_whitespace(keyword("ONLY"))))
+                        .and_then(|_| with_whitespace(schema_name_table_name()))
+                        .and_then(move |table_name| {
+                            optional(
+                                with_whitespace(keyword("USING"))
+                                    .and_then(|_| with_whitespace(name())),
+                            )
+                            .and_then(|_| with_whitespace(match_char('(')))
+                            .and_then(|_| {
+                                comma_sep(with_whitespace(name()).and_then(|col| {
+                                    optional(
+                                        with_whitespace(keyword("ASC"))
+                                            .or(with_whitespace(keyword("DESC"))),
+                                    )
+                                    .map(move |_| col)
+                                }))
+                            })
+                            .and_then(move |columns| {
+                                with_whitespace(match_char(')')).map(move |_| {
+                                    Statement::CreateIndex {
+                                        table: table_name.to_string(),
+                                        index: Index {
+                                            name: index_name.to_string(),
+                                            columns: columns
+                                                .iter()
+                                                .map(|c| c.to_string())
+                                                .collect(),
+                                            unique,
+                                        },
+                                    }
+                                })
+                            })
+                        })
+                })
+        })
+}
+
+pub fn drop_index_parser<'a>() -> Parser<'a, Statement> {
+    with_whitespace(keyword("DROP INDEX"))
+        .and_then(|_| optional(with_whitespace(keyword("CONCURRENTLY"))))
+        .and_then(|_| optional(with_whitespace(keyword("IF EXISTS"))))
+        .and_then(|_| comma_sep(with_whitespace(schema_name_table_name())))
+        .and_then(|indexes| {
+            drop_behaviour()
+                .map(move |_| Statement::DropIndex(indexes.iter().map(|i| i.to_string()).collect()))
+        })
+}
+
+pub fn comment_parser<'a>() -> Parser<'a, Statement> {
+    let table = with_whitespace(keyword("COMMENT ON TABLE"))
+        .and_then(|_| with_whitespace(qualified_name()))
+        .map(|segments| CommentTarget::Table(segments[segments.len() - 1].to_string()));
+    let column = with_whitespace(keyword("COMMENT ON COLUMN"))
+        .and_then(|_| with_whitespace(qualified_name()))
+        .and_then(|segments| {
+            if segments.len() < 2 {
+                return Parser::new(|input| {
+                    Err(ParseError(format!("Expected table.column in: {}", input)))
+                });
+            }
+            let target = CommentTarget::Column {
+                table: segments[segments.len() - 2].to_string(),
+                column: segments[segments.len() - 1].to_string(),
+            };
+            Parser::new(move |input| Ok((target.clone(), input)))
+        });
+    table.or(column).and_then(|target| {
+        with_whitespace(keyword("IS")).and_then(move |_| {
+            let target = target.clone();
+            with_whitespace(string_literal())
+                .map(Some)
+                .or(with_whitespace(keyword("NULL")).map(|_| None))
+                .map(move |comment| Statement::Comment {
+                    target: target.clone(),
+                    comment,
+                })
+        })
+    })
+}
+
+pub fn statement_parser<'a>() -> Parser<'a, Statement> {
+    create_table_parser()
+        .map(Statement::CreateTable)
+        .or(alter_table_parser())
+        .or(drop_table_parser())
+        .or(create_enum_parser())
+        .or(alter_enum_parser())
+        .or(drop_type_parser())
+        .or(create_index_parser())
+        .or(drop_index_parser())
+        .or(comment_parser())
+}
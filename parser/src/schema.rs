@@ -0,0 +1,542 @@
+use crate::{
+    statement_parser, AlterTableAction, Column, CommentTarget, Constraint, Enum, ForeignKey,
+    Statement, Table, Unique,
+};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The set of tables and enum types produced by replaying DDL statements in
+/// order, or by introspecting a live database.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Schema {
+    pub tables: Vec<Table>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub enums: Vec<Enum>,
+}
+
+/// A statement that changes the table model but could not be parsed in full.
+#[derive(Debug, Clone)]
+pub struct SchemaError {
+    /// The migration the statement came from, if it was read from a file.
+    pub file: Option<PathBuf>,
+    pub statement: String,
+    /// What was left once the parser stopped; the whole statement if it
+    /// did not parse at all.
+    pub unparsed: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let collapse = |s: &str| s.split_whitespace().collect::<Vec<_>>().join(" ");
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file.display())?;
+        }
+        write!(
+            f,
+            "cannot parse `{}` in `{}`",
+            collapse(&self.unparsed),
+            collapse(&self.statement)
+        )
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+// CREATE, ALTER and DROP TABLE statements, which must be understood for the
+// model to be right. Everything else may be skipped.
+fn changes_tables(statement: &str) -> bool {
+    let mut words = statement.split_whitespace().map(str::to_ascii_uppercase);
+    if !matches!(words.next().as_deref(), Some("CREATE" | "ALTER" | "DROP")) {
+        return false;
+    }
+    words
+        .find(|w| {
+            !matches!(
+                w.as_str(),
+                "GLOBAL" | "LOCAL" | "TEMP" | "TEMPORARY" | "UNLOGGED"
+            )
+        })
+        .is_some_and(|w| w == "TABLE")
+}
+
+// Postgres folds unquoted identifiers to lower case, so `TEST`, `test` and
+// `"test"` all refer to the same table unless quoting says otherwise.
+fn same_ident(a: &str, b: &str) -> bool {
+    a.trim_matches('"')
+        .eq_ignore_ascii_case(b.trim_matches('"'))
+}
+
+impl Schema {
+    pub fn new() -> Self {
+        Schema {
+            tables: Vec::new(),
+            enums: Vec::new(),
+        }
+    }
+
+    /// Reads a snapshot written by [`Schema::write_snapshot`].
+    #[cfg(feature = "serde")]
+    pub fn read_snapshot<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Writes the schema as pretty-printed JSON so it can be consumed at
+    /// compile time without a database connection.
+    #[cfg(feature = "serde")]
+    pub fn write_snapshot<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, content + "\n")
+    }
+
+    /// Builds a schema from a single SQL script such as a `pg_dump --schema-only` file.
+    pub fn from_sql(sql: &str) -> Result<Self, SchemaError> {
+        let mut schema = Schema::new();
+        schema.apply_sql(sql)?;
+        Ok(schema)
+    }
+
+    /// Replays every migration in `dir`, oldest first.
+    pub fn from_migrations_dir<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        let mut schema = Schema::new();
+        for file in migration_files(dir)? {
+            schema.apply_sql(&fs::read_to_string(&file)?).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    SchemaError {
+                        file: Some(file.clone()),
+                        ..e
+                    },
+                )
+            })?;
+        }
+        Ok(schema)
+    }
+
+    pub fn table(&self, name: &str) -> Option<&Table> {
+        self.tables.iter().find(|t| same_ident(&t.name, name))
+    }
+
+    fn table_mut(&mut self, name: &str) -> Option<&mut Table> {
+        self.tables.iter_mut().find(|t| same_ident(&t.name, name))
+    }
+
+    // Foreign keys, in any table, that point at `table`.
+    fn references_to<'a>(&'a mut self, table: &'a str) -> impl Iterator<Item = &'a mut ForeignKey> {
+        self.tables
+            .iter_mut()
+            .flat_map(|t| t.constraints.iter_mut())
+            .filter_map(move |c| match c {
+                Constraint::ForeignKey(fk) if same_ident(&fk.target_table, table) => Some(fk),
+                _ => None,
+            })
+    }
+
+    // An inline `REFERENCES brands` without columns targets the primary key
+    // the referenced table has when the statement runs.
+    fn resolve_references(&mut self) {
+        let unresolved: Vec<String> = self
+            .tables
+            .iter()
+            .flat_map(|t| t.foreign_keys())
+            .filter(|fk| fk.target_columns.is_empty())
+            .map(|fk| fk.target_table)
+            .collect();
+        for target in unresolved {
+            let Some(primary_key) = self.table(&target).map(Table::primary_key) else {
+                continue;
+            };
+            for fk in self.references_to(&target) {
+                if fk.target_columns.is_empty() {
+                    fk.target_columns = primary_key.clone();
+                }
+            }
+        }
+    }
+
+    pub fn enum_type(&self, name: &str) -> Option<&Enum> {
+        self.enums.iter().find(|e| same_ident(&e.name, name))
+    }
+
+    /// Applies every statement in `sql`. Statements that do not change the
+    /// table model (INSERT, functions, ...) are skipped when they cannot be
+    /// parsed; a CREATE, ALTER or DROP TABLE that cannot is an error.
+    pub fn apply_sql(&mut self, sql: &str) -> Result<(), SchemaError> {
+        for stmt in split_statements(sql) {
+            match statement_parser().parse(&stmt) {
+                Ok((statement, rest)) if rest.trim().is_empty() || !changes_tables(&stmt) => {
+                    self.apply(statement)
+                }
+                Ok((_, rest)) => {
+                    return Err(SchemaError {
+                        file: None,
+                        unparsed: rest.to_string(),
+                        statement: stmt.clone(),
+                    })
+                }
+                Err(_) if changes_tables(&stmt) => {
+                    return Err(SchemaError {
+                        file: None,
+                        unparsed: stmt.clone(),
+                        statement: stmt.clone(),
+                    })
+                }
+                Err(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub fn apply(&mut self, statement: Statement) {
+        match statement {
+            Statement::CreateTable(table) => {
+                self.tables.retain(|t| !same_ident(&t.name, &table.name));
+                self.tables.push(table);
+                self.resolve_references();
+            }
+            Statement::DropTable(names) => self
+                .tables
+                .retain(|t| !names.iter().any(|n| same_ident(&t.name, n))),
+            Statement::AlterTable { table, actions } => {
+                let mut name = table;
+                for action in actions {
+                    let Some(table) = self.table_mut(&name) else {
+                        break;
+                    };
+                    table.apply(action.clone());
+                    // Foreign keys of other tables name the table and its columns too.
+                    match action {
+                        AlterTableAction::RenameColumn { from, to } => {
+                            for fk in self.references_to(&name) {
+                                for col in fk.target_columns.iter_mut() {
+                                    if same_ident(col, &from) {
+                                        *col = to.clone();
+                                    }
+                                }
+                            }
+                        }
+                        AlterTableAction::RenameTo(to) => {
+                            for fk in self.references_to(&name) {
+                                fk.target_table = to.clone();
+                            }
+                            name = to;
+                        }
+                        _ => {}
+                    }
+                }
+                self.resolve_references();
+            }
+            Statement::CreateEnum(enum_type) => {
+                self.enums.retain(|e| !same_ident(&e.name, &enum_type.name));
+                self.enums.push(enum_type);
+            }
+            Statement::AddEnumValue { name, value } => {
+                if let Some(enum_type) = self.enums.iter_mut().find(|e| same_ident(&e.name, &name))
+                {
+                    if !enum_type.values.contains(&value) {
+                        enum_type.values.push(value);
+                    }
+                }
+            }
+            Statement::DropType(names) => self
+                .enums
+                .retain(|e| !names.iter().any(|n| same_ident(&e.name, n))),
+            Statement::CreateIndex { table, index } => {
+                if let Some(table) = self.table_mut(&table) {
+                    table.indexes.retain(|i| !same_ident(&i.name, &index.name));
+                    table.indexes.push(index);
+                }
+            }
+            Statement::DropIndex(names) => {
+                for table in self.tables.iter_mut() {
+                    table
+                        .indexes
+                        .retain(|i| !names.iter().any(|n| same_ident(&i.name, n)));
+                }
+            }
+            Statement::Comment { target, comment } => match target {
+                CommentTarget::Table(table) => {
+                    if let Some(table) = self.table_mut(&table) {
+                        table.comment = comment;
+                    }
+                }
+                CommentTarget::Column { table, column } => {
+                    if let Some(column) = self
+                        .table_mut(&table)
+                        .and_then(|table| table.column_mut(&column))
+                    {
+                        column.comment = comment;
+                    }
+                }
+            },
+        }
+    }
+}
+
+impl Table {
+    pub fn column(&self, name: &str) -> Option<&Column> {
+        self.columns.iter().find(|c| same_ident(&c.name, name))
+    }
+
+    /// Primary key columns, from a table constraint or an inline `PRIMARY KEY`.
+    pub fn primary_key(&self) -> Vec<String> {
+        let constraint = self.constraints.iter().find_map(|c| match c {
+            Constraint::PrimaryKey(pk) => Some(pk.columns.clone()),
+            _ => None,
+        });
+        constraint.unwrap_or_else(|| {
+            self.columns
+                .iter()
+                .filter(|c| {
+                    c.options
+                        .iter()
+                        .any(|o| o.eq_ignore_ascii_case("PRIMARY KEY"))
+                })
+                .map(|c| c.name.clone())
+                .collect()
+        })
+    }
+
+    pub fn is_primary_key(&self, column: &str) -> bool {
+        self.primary_key().iter().any(|c| same_ident(c, column))
+    }
+
+    /// Column sets that must be unique: `UNIQUE` constraints, inline `UNIQUE`
+    /// columns and unique indexes. Inline ones have no name.
+    pub fn unique_keys(&self) -> Vec<Unique> {
+        let mut keys: Vec<Unique> = self
+            .constraints
+            .iter()
+            .filter_map(|c| match c {
+                Constraint::Unique(unique) => Some(unique.clone()),
+                _ => None,
+            })
+            .collect();
+        for column in &self.columns {
+            if column
+                .options
+                .iter()
+                .any(|o| o.eq_ignore_ascii_case("UNIQUE"))
+            {
+                keys.push(Unique {
+                    name: None,
+                    columns: vec![column.name.clone()],
+                });
+            }
+        }
+        for index in self.indexes.iter().filter(|i| i.unique) {
+            keys.push(Unique {
+                name: Some(index.name.clone()),
+                columns: index.columns.clone(),
+            });
+        }
+        keys
+    }
+
+    /// `FOREIGN KEY` constraints, in declaration order.
+    pub fn foreign_keys(&self) -> Vec<ForeignKey> {
+        self.constraints
+            .iter()
+            .filter_map(|c| match c {
+                Constraint::ForeignKey(fk) => Some(fk.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// False for columns declared `NOT NULL` and for primary key columns,
+    /// which Postgres makes `NOT NULL` implicitly.
+    pub fn is_nullable(&self, column: &Column) -> bool {
+        !column.is_not_null() && !self.is_primary_key(&column.name)
+    }
+
+    fn column_mut(&mut self, name: &str) -> Option<&mut Column> {
+        self.columns.iter_mut().find(|c| same_ident(&c.name, name))
+    }
+
+    pub fn apply(&mut self, action: AlterTableAction) {
+        match action {
+            AlterTableAction::AddColumn(column) => {
+                if self.column(&column.name).is_none() {
+                    self.columns.push(column);
+                }
+            }
+            AlterTableAction::DropColumn(name) => {
+                self.columns.retain(|c| !same_ident(&c.name, &name));
+                // Postgres drops single-table constraints that depended on the column.
+                self.constraints.retain(|c| {
+                    !constraint_columns(c)
+                        .iter()
+                        .any(|col| same_ident(col, &name))
+                });
+                self.indexes
+                    .retain(|i| !i.columns.iter().any(|col| same_ident(col, &name)));
+            }
+            AlterTableAction::RenameColumn { from, to } => {
+                if let Some(column) = self.column_mut(&from) {
+                    column.name = to.clone();
+                }
+                let index_columns = self.indexes.iter_mut().map(|i| i.columns.as_mut_slice());
+                for columns in self
+                    .constraints
+                    .iter_mut()
+                    .map(constraint_columns_mut)
+                    .chain(index_columns)
+                {
+                    for col in columns {
+                        if same_ident(col, &from) {
+                            *col = to.clone();
+                        }
+                    }
+                }
+            }
+            AlterTableAction::RenameTo(name) => self.name = name,
+            AlterTableAction::AlterColumnType { column, dtype } => {
+                if let Some(column) = self.column_mut(&column) {
+                    column.dtype = dtype;
+                }
+            }
+            AlterTableAction::SetDefault { column, default } => {
+                if let Some(column) = self.column_mut(&column) {
+                    column.default = Some(default);
+                }
+            }
+            AlterTableAction::DropDefault(column) => {
+                if let Some(column) = self.column_mut(&column) {
+                    column.default = None;
+                }
+            }
+            AlterTableAction::SetNotNull(column) => {
+                if let Some(column) = self.column_mut(&column) {
+                    if !column.is_not_null() {
+                        column.options.push("NOT NULL".to_string());
+                    }
+                }
+            }
+            AlterTableAction::DropNotNull(column) => {
+                if let Some(column) = self.column_mut(&column) {
+                    column
+                        .options
+                        .retain(|o| !o.eq_ignore_ascii_case("NOT NULL"));
+                }
+            }
+            AlterTableAction::AddConstraint(constraint) => self.constraints.push(constraint),
+            AlterTableAction::DropConstraint(name) => self
+                .constraints
+                .retain(|c| !c.name().is_some_and(|n| same_ident(n, &name))),
+            AlterTableAction::RenameConstraint { from, to } => {
+                for constraint in self.constraints.iter_mut() {
+                    if constraint.name().is_some_and(|n| same_ident(n, &from)) {
+                        *constraint = constraint.clone().named(&to);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn constraint_columns(constraint: &Constraint) -> &[String] {
+    match constraint {
+        Constraint::PrimaryKey(pk) => &pk.columns,
+        Constraint::ForeignKey(fk) => &fk.source_columns,
+        Constraint::Unique(u) => &u.columns,
+        Constraint::Check(_) => &[],
+    }
+}
+
+fn constraint_columns_mut(constraint: &mut Constraint) -> &mut [String] {
+    match constraint {
+        Constraint::PrimaryKey(pk) => &mut pk.columns,
+        Constraint::ForeignKey(fk) => &mut fk.source_columns,
+        Constraint::Unique(u) => &mut u.columns,
+        Constraint::Check(_) => &mut [],
+    }
+}
+
+/// Parses the version prefix of a migration file name.
+///
+/// Accepts refinery style `V3__add_assets.sql` / `U3__...sql` and sqlx style
+/// `20240101120000_add_assets.sql` / `..._add_assets.up.sql`. Returns `None`
+/// for anything else, including sqlx `.down.sql` files.
+pub fn migration_version(file_name: &str) -> Option<u64> {
+    let stem = file_name.strip_suffix(".sql")?;
+    if stem.ends_with(".down") {
+        return None;
+    }
+    let digits = stem
+        .strip_prefix('V')
+        .or_else(|| stem.strip_prefix('U'))
+        .unwrap_or(stem);
+    let end = digits.find(|c: char| !c.is_ascii_digit())?;
+    if end == 0 || !digits[end..].starts_with('_') {
+        return None;
+    }
+    digits[..end].parse().ok()
+}
+
+/// Lists the migration files in `dir` in the order they would be applied.
+pub fn migration_files<P: AsRef<Path>>(dir: P) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let version = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(migration_version);
+        if let (true, Some(version)) = (path.is_file(), version) {
+            files.push((version, path));
+        }
+    }
+    files.sort();
+    Ok(files.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Splits a SQL script on `;`, ignoring semicolons inside comments, string
+/// literals, quoted identifiers and dollar-quoted function bodies. Comments are
+/// dropped from the returned statements.
+pub fn split_statements(sql: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut rest = sql;
+
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("--") {
+            rest = rest.find('\n').map_or("", |i| &rest[i..]);
+        } else if rest.starts_with("/*") {
+            rest = rest[2..].find("*/").map_or("", |i| &rest[i + 4..]);
+            current.push(' ');
+        } else if c == '\'' || c == '"' {
+            let end = rest[1..].find(c).map_or(rest.len(), |i| i + 2);
+            current.push_str(&rest[..end]);
+            rest = &rest[end..];
+        } else if let Some(tag) = dollar_quote_tag(rest) {
+            let end = rest[tag.len()..]
+                .find(tag)
+                .map_or(rest.len(), |i| i + 2 * tag.len());
+            current.push_str(&rest[..end]);
+            rest = &rest[end..];
+        } else if c == ';' {
+            statements.push(std::mem::take(&mut current));
+            rest = &rest[1..];
+        } else {
+            current.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    statements.push(current);
+    statements.retain(|s| !s.trim().is_empty());
+    statements
+}
+
+fn dollar_quote_tag(input: &str) -> Option<&str> {
+    let body = input.strip_prefix('$')?;
+    let end = body.find('$')?;
+    body[..end]
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '_')
+        .then(|| &input[..end + 2])
+}
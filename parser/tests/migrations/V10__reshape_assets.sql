@@ -0,0 +1,10 @@
+ALTER TABLE public.assets RENAME COLUMN brand TO asset_brand;
+ALTER TABLE public.assets
+	ADD COLUMN created_at timestamptz DEFAULT now() NOT NULL,
+	ADD CONSTRAINT assets_brand_fk FOREIGN KEY (asset_brand) REFERENCES public.brands(brand_id),
+	ALTER COLUMN asset_brand SET NOT NULL;
+ALTER TABLE brands DROP CONSTRAINT brands_brand_name_key;
+ALTER TABLE brands DROP COLUMN brand_name;
+ALTER TABLE assets ALTER COLUMN asset_name TYPE varchar USING asset_name::varchar;
+DROP TABLE IF EXISTS legacy_imports CASCADE;
+ALTER TABLE brands RENAME TO asset_brands;
@@ -0,0 +1 @@
+ALTER TABLE asset_brands RENAME COLUMN brand_id TO asset_brand_id;
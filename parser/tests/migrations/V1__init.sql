@@ -0,0 +1,14 @@
+-- Initial schema
+CREATE TABLE public.brands (
+	brand_id text NOT NULL,
+	brand_name text NOT NULL,
+	CONSTRAINT brand_pkey PRIMARY KEY (brand_id),
+	CONSTRAINT brands_brand_name_key UNIQUE (brand_name)
+);
+
+CREATE TABLE legacy_imports (
+	import_id int PRIMARY KEY,
+	payload text
+);
+
+COMMENT ON TABLE public.brands IS 'Brands; owned by marketing';
@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS assets (
+	asset_id uuid NOT NULL DEFAULT gen_random_uuid(),
+	asset_name text NOT NULL,
+	brand text,
+	PRIMARY KEY (asset_id)
+);
+
+CREATE INDEX assets_name_idx ON assets (asset_name);
+
+CREATE FUNCTION touch() RETURNS trigger AS $$
+BEGIN
+	NEW.asset_name := NEW.asset_name;
+	RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
@@ -1,6 +1,8 @@
 use parse::{
-    cascade, column, comma_sep, constraint, create_table_parser, foreign_key, function, match_char,
-    match_string, name, with_whitespace, ForeignKey, ParseError, Parser,
+    alter_table_parser, cascade, column, comma_sep, constraint, create_table_parser, data_type,
+    foreign_key, function, match_char, match_string, migration_version, name, split_statements,
+    with_whitespace, AlterTableAction, Constraint, ForeignKey, ParseError, Parser, Schema,
+    Statement,
 };
 use std::sync::Arc;
 mod tests {
@@ -122,4 +124,246 @@ mod tests {
         }
         // Generate structs based on the JSON data
     }
+
+    #[test]
+    fn test_alter_table_parser() {
+        let sql = "ALTER TABLE public.assets ADD COLUMN created_at timestamptz DEFAULT now() NOT NULL, DROP CONSTRAINT assets_brand_fk, RENAME COLUMN brand TO asset_brand";
+        let (statement, _) = alter_table_parser().parse(sql).expect("alter table");
+        match statement {
+            Statement::AlterTable { table, actions } => {
+                assert_eq!(table, "assets");
+                assert_eq!(actions.len(), 3);
+                match &actions[0] {
+                    AlterTableAction::AddColumn(c) => {
+                        assert_eq!(c.name, "created_at");
+                        assert_eq!(c.default.as_deref(), Some("now()"));
+                        assert!(c.is_not_null());
+                    }
+                    other => panic!("{:?}", other),
+                }
+                assert!(
+                    matches!(&actions[1], AlterTableAction::DropConstraint(n) if n == "assets_brand_fk")
+                );
+                assert!(
+                    matches!(&actions[2], AlterTableAction::RenameColumn { from, to } if from == "brand" && to == "asset_brand")
+                );
+            }
+            other => panic!("{:?}", other),
+        }
+
+        // `RENAME TO` must not swallow a column whose name starts with "to".
+        let (statement, _) = alter_table_parser()
+            .parse("ALTER TABLE t RENAME total TO grand_total")
+            .expect("rename column");
+        assert!(matches!(statement, Statement::AlterTable { actions, .. }
+            if matches!(&actions[0], AlterTableAction::RenameColumn { from, .. } if from == "total")));
+    }
+
+    #[test]
+    fn test_split_statements() {
+        let sql = "CREATE TABLE a (id int); -- trailing; comment\nCOMMENT ON TABLE a IS 'x; y'; CREATE FUNCTION f() AS $$ BEGIN; END; $$;";
+        let statements = split_statements(sql);
+        assert_eq!(statements.len(), 3);
+        assert!(statements[1].contains("'x; y'"));
+        assert!(statements[2].contains("BEGIN; END;"));
+    }
+
+    #[test]
+    fn test_migration_version() {
+        assert_eq!(migration_version("V1__init.sql"), Some(1));
+        assert_eq!(migration_version("V10__reshape.sql"), Some(10));
+        assert_eq!(
+            migration_version("20240101120000_init.up.sql"),
+            Some(20240101120000)
+        );
+        assert_eq!(migration_version("20240101120000_init.down.sql"), None);
+        assert_eq!(migration_version("README.md"), None);
+        assert_eq!(migration_version("seed.sql"), None);
+    }
+
+    #[test]
+    fn test_migrations_dir() {
+        let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR not set");
+        let schema =
+            Schema::from_migrations_dir(PathBuf::from(manifest_dir).join("tests/migrations"))
+                .expect("Unable to read migrations");
+
+        assert!(schema.table("legacy_imports").is_none());
+        assert!(schema.table("brands").is_none());
+
+        let brands = schema.table("asset_brands").expect("renamed brands table");
+        assert_eq!(brands.columns.len(), 1);
+        assert_eq!(brands.primary_key(), ["asset_brand_id"]);
+        assert_eq!(brands.constraints.len(), 1);
+        assert_eq!(
+            brands.comment.as_deref(),
+            Some("Brands; owned by marketing")
+        );
+
+        let assets = schema.table("assets").expect("assets table");
+        let names: Vec<&str> = assets.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["asset_id", "asset_name", "asset_brand", "created_at"]
+        );
+        assert_eq!(assets.column("asset_name").unwrap().dtype, "varchar");
+        assert!(assets.column("asset_brand").unwrap().is_not_null());
+        assert_eq!(
+            assets.column("asset_id").unwrap().default.as_deref(),
+            Some("gen_random_uuid()")
+        );
+        // The foreign key follows the renames of the table and column it points at.
+        assert!(assets.constraints.iter().any(|c| matches!(c,
+            Constraint::ForeignKey(fk) if fk.name.as_deref() == Some("assets_brand_fk")
+                && fk.source_columns == ["asset_brand"]
+                && fk.target_table == "asset_brands"
+                && fk.target_columns == ["asset_brand_id"])));
+        assert!(assets.constraints.iter().any(|c| matches!(c,
+            Constraint::PrimaryKey(pk) if pk.columns == ["asset_id"])));
+        assert_eq!(assets.indexes.len(), 1);
+        assert_eq!(assets.indexes[0].name, "assets_name_idx");
+    }
+
+    #[test]
+    fn test_table_keys() {
+        let schema = Schema::from_sql(
+            "CREATE TABLE users (
+                user_id serial PRIMARY KEY,
+                email text UNIQUE NOT NULL,
+                org_id int4 NOT NULL,
+                handle text NOT NULL,
+                nickname text,
+                CONSTRAINT users_org_handle_key UNIQUE (org_id, handle)
+            );
+            CREATE UNIQUE INDEX users_nickname_idx ON users (nickname);",
+        )
+        .unwrap();
+        let users = schema.table("users").unwrap();
+        assert_eq!(users.primary_key(), ["user_id"]);
+        assert!(!users.is_nullable(users.column("user_id").unwrap()));
+        assert!(users.is_nullable(users.column("nickname").unwrap()));
+
+        let unique_keys = users.unique_keys();
+        let keys: Vec<(Option<&str>, Vec<&str>)> = unique_keys
+            .iter()
+            .map(|u| {
+                (
+                    u.name.as_deref(),
+                    u.columns.iter().map(|c| c.as_str()).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                (Some("users_org_handle_key"), vec!["org_id", "handle"]),
+                (None, vec!["email"]),
+                (Some("users_nickname_idx"), vec!["nickname"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_foreign_keys() {
+        let schema = Schema::from_sql(
+            "CREATE TABLE practices (practice_id text NOT NULL, CONSTRAINT practices_pkey PRIMARY KEY (practice_id));
+            CREATE TABLE products (
+                product_id text NOT NULL,
+                owning_practice text NULL,
+                CONSTRAINT products_pkey PRIMARY KEY (product_id),
+                CONSTRAINT products_owning_practice_fkey FOREIGN KEY (owning_practice) REFERENCES public.practices(practice_id) ON DELETE CASCADE
+            );",
+        )
+        .unwrap();
+        let products = schema.table("products").unwrap();
+        let foreign_keys = products.foreign_keys();
+        assert_eq!(foreign_keys.len(), 1);
+        assert_eq!(
+            foreign_keys[0].name.as_deref(),
+            Some("products_owning_practice_fkey")
+        );
+        assert_eq!(foreign_keys[0].source_columns, ["owning_practice"]);
+        assert_eq!(foreign_keys[0].target_table, "practices");
+        assert_eq!(foreign_keys[0].target_columns, ["practice_id"]);
+        assert!(schema.table("practices").unwrap().foreign_keys().is_empty());
+    }
+
+    #[test]
+    fn test_column_constraints() {
+        let schema = Schema::from_sql(
+            "CREATE SEQUENCE assets_seq;
+            CREATE TABLE brands (brand_id text PRIMARY KEY);
+            CREATE TABLE assets (
+                asset_id int8 DEFAULT nextval('assets_seq'::regclass) NOT NULL,
+                status character varying DEFAULT 'it''s, draft'::character varying NOT NULL CHECK (status IN ('draft', 'live')),
+                brand_id text REFERENCES brands ON DELETE SET NULL,
+                CONSTRAINT assets_pkey PRIMARY KEY (asset_id),
+                CHECK (length(status) > 0)
+            );
+            ALTER TABLE assets ADD CONSTRAINT assets_id_chk CHECK (asset_id > 0),
+                ALTER COLUMN status SET DEFAULT 'live';
+            INSERT INTO brands VALUES ('ibm');",
+        )
+        .unwrap();
+        let assets = schema.table("assets").unwrap();
+        assert_eq!(
+            assets.column("asset_id").unwrap().default.as_deref(),
+            Some("nextval('assets_seq'::regclass)")
+        );
+        assert_eq!(
+            assets.column("status").unwrap().default.as_deref(),
+            Some("'live'")
+        );
+        assert!(assets.column("status").unwrap().is_not_null());
+        let foreign_keys = assets.foreign_keys();
+        assert_eq!(foreign_keys.len(), 1);
+        assert_eq!(foreign_keys[0].source_columns, ["brand_id"]);
+        assert_eq!(foreign_keys[0].target_table, "brands");
+        assert_eq!(foreign_keys[0].target_columns, ["brand_id"]);
+        assert!(assets.constraints.iter().any(|c| matches!(c,
+            Constraint::Check(check) if check.name.as_deref() == Some("assets_id_chk")
+                && check.expression == "asset_id > 0")));
+        assert_eq!(assets.primary_key(), ["asset_id"]);
+    }
+
+    #[test]
+    fn test_unparsed_table_statement() {
+        let err = Schema::from_sql(
+            "CREATE TABLE brands (brand_id text PRIMARY KEY);
+            CREATE INDEX brands_idx ON brands USING gin (brand_id gin_trgm_ops);
+            ALTER TABLE brands ALTER COLUMN brand_id SET STATISTICS 100;",
+        )
+        .unwrap_err();
+        assert!(err.file.is_none());
+        assert!(err.statement.contains("SET STATISTICS 100"));
+        assert!(err.to_string().contains("ALTER TABLE brands"));
+    }
+
+    #[test]
+    fn test_data_type_parser() {
+        for (input, expected) in [
+            ("text NOT NULL", "text"),
+            ("varchar(255) NOT NULL", "varchar(255)"),
+            ("numeric(10, 2)", "numeric(10,2)"),
+            ("double precision,", "double precision"),
+            ("character varying(20)", "character varying(20)"),
+            (
+                "timestamp(3) with time zone DEFAULT now()",
+                "timestamp(3) with time zone",
+            ),
+            ("text[] NULL", "text[]"),
+            ("_text NULL", "_text"),
+        ] {
+            let (dtype, _) = data_type().parse(input).expect(input);
+            assert_eq!(dtype, expected);
+        }
+
+        let (parsed, rest) = create_table_parser()
+            .parse("CREATE TABLE prices (amount numeric(10, 2) NOT NULL, tags text[])")
+            .expect("create table");
+        assert_eq!(rest, "");
+        assert_eq!(parsed.columns[0].dtype, "numeric(10,2)");
+        assert!(parsed.columns[0].is_not_null());
+        assert_eq!(parsed.columns[1].dtype, "text[]");
+    }
 }